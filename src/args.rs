@@ -1,48 +1,191 @@
+use core::fmt;
 use std::{
     collections::{
         HashMap,
         hash_map::{Iter as MapIter, IterMut as MapIterMut},
     },
+    error::Error,
+    iter::{Enumerate, Peekable},
     ops::Range,
+    path::PathBuf,
     slice::{Iter, IterMut},
     str::FromStr,
+    vec::IntoIter,
 };
 
-use crate::{Command, HelpReason};
+use crate::{Command, CommandError, Help, ValueName, value::AnyValue};
 
+/// result of parsing: the matched command, its arguments and the closest help function.
+type Parsed<'a> = (&'a Command, Args, Option<&'a Help>);
+/// remaining command line arguments, with their indexes.
+type Remaining = Peekable<Enumerate<IntoIter<String>>>;
+
+#[derive(Debug, Clone, PartialEq)]
+/// where the value of an option came from.
+pub enum ValueSource {
+    /// given on the command line, at this index of the arguments.
+    CommandLine(usize),
+    /// read from this environment variable.
+    Env(String),
+    /// read from a config file, at this path and line.
+    Config(PathBuf, usize),
+    /// the option's default value.
+    Default,
+}
+
+impl fmt::Display for ValueSource {
+    /// formats a value source.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CommandLine(index) => write!(f, "command line, argument {index}"),
+            Self::Env(var) => write!(f, "environment variable {var}"),
+            Self::Config(path, line) => write!(f, "config file {}:{}", path.display(), line),
+            Self::Default => write!(f, "default"),
+        }
+    }
+}
+
+#[derive(Debug)]
+/// reasons that getting an argument value might've failed.
+pub enum ArgError {
+    /// no value with this option name or positional index (written as `#index`).
+    Missing(String),
+    /// the value couldn't be parsed into the requested type.
+    Invalid {
+        /// option name or positional index (written as `#index`).
+        name: String,
+        /// value that couldn't be parsed.
+        value: String,
+        /// error returned by the parser.
+        source: Box<dyn Error + Send + Sync>,
+    },
+    /// a range of positional arguments goes past the last argument.
+    OutOfRange {
+        /// requested range.
+        range: Range<usize>,
+        /// number of positional arguments.
+        len: usize,
+    },
+}
+
+impl fmt::Display for ArgError {
+    /// formats an argument error.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing(name) => write!(f, "Missing value for {name}"),
+            Self::Invalid {
+                name,
+                value,
+                source,
+            } => write!(f, "Invalid value {value:?} for {name}: {source}"),
+            Self::OutOfRange { range, len } => write!(
+                f,
+                "Arguments {} to {} are out of range, only {} given",
+                range.start, range.end, len
+            ),
+        }
+    }
+}
+
+impl Error for ArgError {
+    /// gets the parser error for invalid values.
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Invalid { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// parses a value, turning failures into `ArgError::Invalid`.
+fn parse_value<T>(name: String, value: &str) -> Result<T, ArgError>
+where
+    T: FromStr,
+    T::Err: Error + Send + Sync + 'static,
+{
+    value.parse::<T>().map_err(|error| ArgError::Invalid {
+        name,
+        value: value.to_string(),
+        source: Box::new(error),
+    })
+}
+
+#[derive(Clone)]
 /// stores parsed command line arguments.
 pub struct Args {
     /// map of option names to their values.
     pub opts: HashMap<String, String>,
     /// list of positional arguments.
     pub pos: Vec<String>,
+    /// index in the command line of every positional argument.
+    pub(crate) pos_index: Vec<usize>,
+    /// depth of the command every positional argument was given to, where the root is 0.
+    pub(crate) pos_depth: Vec<usize>,
+    /// depth of the command every option was given to or resolved for.
+    pub(crate) depths: HashMap<String, usize>,
+    /// index in the command line of the token holding every option's value, or of the option
+    /// itself when it has no value.
+    pub(crate) indexes: HashMap<String, usize>,
+    /// the command line that was parsed.
+    pub(crate) argv: Vec<String>,
+    /// indexes of the subcommands taken from the root command.
+    pub(crate) path: Vec<usize>,
+    /// index in the command line of every subcommand name.
+    pub(crate) path_index: Vec<usize>,
+    /// names of the matched commands, starting with the root.
+    pub(crate) commands: Vec<String>,
+    /// index in the command line of the `help` subcommand, if it was given.
+    pub(crate) help_index: Option<usize>,
+    /// where each option value came from.
+    pub(crate) sources: HashMap<String, ValueSource>,
+    /// errors found while parsing, reported when the command runs.
+    pub(crate) errors: Vec<CommandError>,
+    /// ranges of positional values taken by each named argument.
+    pub(crate) names: HashMap<String, Range<usize>>,
+    /// option values parsed by their value parsers.
+    pub(crate) typed: HashMap<String, AnyValue>,
+    /// positional argument values parsed by their value parsers.
+    pub(crate) typed_pos: HashMap<usize, AnyValue>,
 }
 
 impl Args {
     /// parses command line arguments for the given command.
-    pub fn parse<'a>(
-        command: &'a Command,
-        arguments: Vec<String>,
-    ) -> (
-        &'a Command,
-        Args,
-        Option<&'a Box<dyn Fn(HelpReason, &'a Command, Args)>>,
-    ) {
+    pub fn parse(command: &Command, arguments: Vec<String>) -> Parsed<'_> {
         let mut current_command = command;
         let mut parsed_args = Args {
             opts: HashMap::new(),
             pos: Vec::new(),
+            pos_index: Vec::new(),
+            pos_depth: Vec::new(),
+            depths: HashMap::new(),
+            indexes: HashMap::new(),
+            argv: arguments.clone(),
+            path: Vec::new(),
+            path_index: Vec::new(),
+            commands: vec![command.names[0].clone()],
+            help_index: None,
+            sources: HashMap::new(),
+            typed: HashMap::new(),
+            typed_pos: HashMap::new(),
+            names: HashMap::new(),
+            errors: Vec::new(),
         };
-        let mut help_fn = None;
+        let mut help_fn = command.help.as_ref();
+        let mut lineage = vec![command];
 
         let mut ignore_options = false;
 
-        for arg in arguments {
+        let mut remaining = arguments.into_iter().enumerate().peekable();
+        while let Some((index, arg)) = remaining.next() {
             // tries to match argument as a subcommand of current_command.
             let mut is_subcommand = false;
-            for cmd in &current_command.children {
+            for (i, cmd) in current_command.children.iter().enumerate() {
                 if cmd.names.iter().any(|alias| alias == &arg) {
                     current_command = cmd;
+                    lineage.push(cmd);
+                    parsed_args.path.push(i);
+                    parsed_args.path_index.push(index);
+                    parsed_args.commands.push(cmd.names[0].clone());
                     help_fn = cmd.help.as_ref().or(help_fn);
                     is_subcommand = true;
                     break;
@@ -54,25 +197,99 @@ impl Args {
                 continue;
             }
 
+            // treats `help` as the help subcommand, which takes the subcommands after it as the
+            // command to show help for.
+            if !ignore_options
+                && arg == "help"
+                && parsed_args.help_index.is_none()
+                && !current_command.children.is_empty()
+                && parsed_args.pos_depth.last() != Some(&parsed_args.path.len())
+                && Command::help_command_of(&lineage)
+            {
+                parsed_args.help_index = Some(index);
+                continue;
+            }
+
             if !ignore_options {
                 if arg == "--" {
                     // disables option parsing after '--'.
                     ignore_options = true;
                     continue;
                 } else if arg.starts_with("--") {
-                    // parses long option with optional value.
-                    let split: Vec<&str> = arg.splitn(2, '=').collect();
-                    let name = split[0];
-                    let value = split.get(1).unwrap_or(&"true");
-                    parsed_args.opts.insert(name.to_string(), value.to_string());
+                    // parses long option, taking its value from after '=' or the next argument.
+                    let (name, value) = match arg.split_once('=') {
+                        Some((name, value)) => (name, Some((index, value.to_string()))),
+                        None => (arg.as_str(), None),
+                    };
+                    let value = value.or_else(|| {
+                        Self::take_value(&lineage, current_command, name, index, &mut remaining)
+                    });
+                    match value {
+                        Some((value_index, value)) => {
+                            let depth = Self::depth(&lineage, name);
+                            parsed_args.set(name, &value, ValueSource::CommandLine(index), depth);
+                            parsed_args.indexes.insert(name.to_string(), value_index);
+                        }
+                        None => {
+                            parsed_args.indexes.insert(name.to_string(), index);
+                            parsed_args
+                                .errors
+                                .push(CommandError::MissingValue(name.to_string()));
+                        }
+                    }
                     continue;
                 } else if arg.starts_with('-') {
                     // parses one or more short options with optional value.
                     let split: Vec<&str> = arg.splitn(2, '=').collect();
                     let chars: Vec<char> = split[0].chars().skip(1).collect(); // skip leading '-'
-                    let value = split.get(1).unwrap_or(&"true");
-                    for ch in chars {
-                        parsed_args.opts.insert(format!("-{ch}"), value.to_string());
+                    if let Some(value) = split.get(1) {
+                        for ch in chars {
+                            let name = format!("-{ch}");
+                            let depth = Self::depth(&lineage, &name);
+                            parsed_args.set(&name, value, ValueSource::CommandLine(index), depth);
+                            parsed_args.indexes.insert(name, index);
+                        }
+                        continue;
+                    }
+
+                    for (i, ch) in chars.iter().enumerate() {
+                        let name = format!("-{ch}");
+                        let depth = Self::depth(&lineage, &name);
+                        if Self::value_name(&lineage, &name).is_none() {
+                            parsed_args.set(&name, "true", ValueSource::CommandLine(index), depth);
+                            parsed_args.indexes.insert(name, index);
+                            continue;
+                        }
+
+                        // an option taking a value takes the rest of the argument, like `-p80`.
+                        let rest: String = chars[i + 1..].iter().collect();
+                        let value = if rest.is_empty() {
+                            Self::take_value(
+                                &lineage,
+                                current_command,
+                                &name,
+                                index,
+                                &mut remaining,
+                            )
+                        } else {
+                            Some((index, rest))
+                        };
+                        match value {
+                            Some((value_index, value)) => {
+                                parsed_args.set(
+                                    &name,
+                                    &value,
+                                    ValueSource::CommandLine(index),
+                                    depth,
+                                );
+                                parsed_args.indexes.insert(name, value_index);
+                            }
+                            None => {
+                                parsed_args.indexes.insert(name.clone(), index);
+                                parsed_args.errors.push(CommandError::MissingValue(name));
+                            }
+                        }
+                        break;
                     }
                     continue;
                 }
@@ -80,20 +297,77 @@ impl Args {
 
             // treats argument as a positional argument.
             parsed_args.pos.push(arg);
+            parsed_args.pos_index.push(index);
+            parsed_args.pos_depth.push(parsed_args.path.len());
+        }
+
+        // maps argument names to the values they captured.
+        let ranges = current_command.ranges_of(&parsed_args);
+        for (arg, range) in current_command.arguments.iter().zip(ranges) {
+            if let Some(name) = &arg.name {
+                parsed_args.names.insert(name.clone(), range);
+            }
         }
 
         (current_command, parsed_args, help_fn)
     }
 
+    /// gets the depth of the closest command in the lineage that has an option, or the matched
+    /// command's depth if none do.
+    fn depth(lineage: &[&Command], name: &str) -> usize {
+        lineage
+            .iter()
+            .rposition(|command| {
+                command
+                    .options
+                    .iter()
+                    .any(|option| option.names.iter().any(|n| n == name))
+            })
+            .unwrap_or(lineage.len() - 1)
+    }
+
+    /// finds the value placeholder of an option, looking at the matched commands from the last.
+    fn value_name<'a>(lineage: &[&'a Command], name: &str) -> Option<&'a ValueName> {
+        lineage
+            .iter()
+            .rev()
+            .flat_map(|command| command.options.iter())
+            .find(|option| option.names.iter().any(|n| n == name))?
+            .value
+            .as_ref()
+    }
+
+    /// takes the value of an option from the next argument when the option takes one. options
+    /// that don't take a value, or whose value is optional and not given, get `true`. returns
+    /// `None` when a required value is missing.
+    fn take_value(
+        lineage: &[&Command],
+        current_command: &Command,
+        name: &str,
+        index: usize,
+        remaining: &mut Remaining,
+    ) -> Option<(usize, String)> {
+        match Self::value_name(lineage, name) {
+            Some(ValueName { required: true, .. }) => remaining.next(),
+            Some(ValueName {
+                required: false, ..
+            }) => {
+                // optional values can't look like options or subcommands.
+                let value = remaining.next_if(|(_, next)| {
+                    !next.starts_with('-')
+                        && !current_command
+                            .children
+                            .iter()
+                            .any(|child| child.names.contains(next))
+                });
+                Some(value.unwrap_or_else(|| (index, "true".to_string())))
+            }
+            None => Some((index, "true".to_string())),
+        }
+    }
+
     /// parses command line arguments from a slice of string slices.
-    pub fn parse_str<'a>(
-        command: &'a Command,
-        arguments: Vec<&str>,
-    ) -> (
-        &'a Command,
-        Args,
-        Option<&'a Box<dyn Fn(HelpReason, &'a Command, Args)>>,
-    ) {
+    pub fn parse_str<'a>(command: &'a Command, arguments: Vec<&str>) -> Parsed<'a> {
         Self::parse(
             command,
             arguments.iter().map(|arg| arg.to_string()).collect(),
@@ -112,6 +386,115 @@ impl Args {
         arguments
     }
 
+    /// sets an option value along with where it came from and the depth of the command it
+    /// belongs to.
+    pub(crate) fn set(&mut self, name: &str, value: &str, source: ValueSource, depth: usize) {
+        self.opts.insert(name.to_string(), value.to_string());
+        self.sources.insert(name.to_string(), source);
+        self.depths.insert(name.to_string(), depth);
+    }
+
+    /// fills an option with the value of another, like a deprecated alias filling the option it
+    /// stands for.
+    pub(crate) fn copy(&mut self, from: &str, to: &str) {
+        let (Some(value), Some(source)) = (self.opts.get(from), self.sources.get(from)) else {
+            return;
+        };
+        let (value, source) = (value.clone(), source.clone());
+        let depth = self.depths.get(from).copied().unwrap_or_default();
+        self.set(to, &value, source, depth);
+        if let Some(index) = self.indexes.get(from).copied() {
+            self.indexes.insert(to.to_string(), index);
+        }
+    }
+
+    /// gets the index of the first positional argument given to the matched command, as the ones
+    /// before it were given to parent commands.
+    pub(crate) fn first_pos(&self) -> usize {
+        let depth = self.path.len();
+        self.pos_depth.iter().filter(|d| **d < depth).count()
+    }
+
+    /// gets the options and positional arguments given to the command at a depth of the matched
+    /// path, where the root is 0.
+    pub(crate) fn level(&self, depth: usize) -> Args {
+        let mut level = self.clone();
+        level.path.truncate(depth);
+        level.path_index.truncate(depth);
+        level.commands.truncate(depth + 1);
+        level.errors.clear();
+
+        let at_depth = |name: &String| self.depths.get(name) == Some(&depth);
+        level.opts.retain(|name, _| at_depth(name));
+        level.sources.retain(|name, _| at_depth(name));
+        level.typed.retain(|name, _| at_depth(name));
+        level.indexes.retain(|name, _| at_depth(name));
+        level.depths.retain(|name, _| at_depth(name));
+
+        let kept: Vec<usize> = (0..self.pos.len())
+            .filter(|i| self.pos_depth[*i] == depth)
+            .collect();
+        level.pos = kept.iter().map(|i| self.pos[*i].clone()).collect();
+        level.pos_index = kept.iter().map(|i| self.pos_index[*i]).collect();
+        level.pos_depth = vec![depth; kept.len()];
+        level.typed_pos = kept
+            .iter()
+            .enumerate()
+            .filter_map(|(new, old)| Some((new, self.typed_pos.get(old)?.clone())))
+            .collect();
+
+        // named arguments belong to the matched command, and skip arguments given before it.
+        if depth == self.path.len() {
+            let skipped = self.first_pos();
+            for range in level.names.values_mut() {
+                *range = range.start.saturating_sub(skipped)..range.end.saturating_sub(skipped);
+            }
+        } else {
+            level.names.clear();
+        }
+
+        level
+    }
+
+    /// gets where the value of an option came from.
+    pub fn source(&self, name: &str) -> Option<&ValueSource> {
+        self.sources.get(name)
+    }
+
+    /// gets the index in the command line of the token holding an option's value, if it was given
+    /// on the command line.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.indexes.get(name).copied()
+    }
+
+    /// gets the names of the matched commands, starting with the root, like `["human", "add"]`.
+    pub fn command_path(&self) -> &[String] {
+        &self.commands
+    }
+
+    /// gets the index in the command line of a positional argument.
+    pub fn index_at(&self, pos: usize) -> Option<usize> {
+        self.pos_index.get(pos).copied()
+    }
+
+    /// lists every option, sorted by name, with its value and where it came from. useful for
+    /// debugging how values were resolved.
+    pub fn debug_dump(&self) -> String {
+        let mut names: Vec<&String> = self.opts.keys().collect();
+        names.sort();
+
+        let mut builder = String::new();
+        for name in names {
+            builder.push_str(&format!("{}={}", name, self.opts[name]));
+            if let Some(source) = self.sources.get(name) {
+                builder.push_str(&format!(" ({source})"));
+            }
+            builder.push('\n');
+        }
+
+        builder
+    }
+
     /// checks if an option with the given name exists.
     pub fn has(&self, name: &str) -> bool {
         self.opts.contains_key(name)
@@ -132,10 +515,7 @@ impl Args {
     where
         T: FromStr,
     {
-        match self.opts.get(name)?.parse::<T>() {
-            Ok(val) => Some(val),
-            Err(_) => None,
-        }
+        self.opts.get(name)?.parse::<T>().ok()
     }
 
     /// tries to get and parse the option value by either name or other into type T.
@@ -150,6 +530,42 @@ impl Args {
         }
     }
 
+    /// gets and parses the option value by name into type T, telling apart missing and malformed
+    /// values.
+    pub fn try_get<T>(&self, name: &str) -> Result<T, ArgError>
+    where
+        T: FromStr,
+        T::Err: Error + Send + Sync + 'static,
+    {
+        let value = self
+            .opts
+            .get(name)
+            .ok_or_else(|| ArgError::Missing(name.to_string()))?;
+        parse_value(name.to_string(), value)
+    }
+
+    /// gets and parses the option value by either name or other into type T.
+    pub fn try_get_or<T>(&self, name: &str, other: &str) -> Result<T, ArgError>
+    where
+        T: FromStr,
+        T::Err: Error + Send + Sync + 'static,
+    {
+        if self.opts.contains_key(name) {
+            self.try_get(name)
+        } else {
+            self.try_get(other).map_err(|error| match error {
+                ArgError::Missing(_) => ArgError::Missing(format!("{name} or {other}")),
+                error => error,
+            })
+        }
+    }
+
+    /// gets the value of an option parsed by its value parser, without parsing it again.
+    /// returns `None` if the option has no value or was parsed into another type.
+    pub fn value<T: 'static>(&self, name: &str) -> Option<&T> {
+        self.typed.get(name)?.downcast_ref::<T>()
+    }
+
     /// gets the option value as a string reference.
     pub fn get_string(&self, name: &str) -> Option<&String> {
         self.opts.get(name)
@@ -169,10 +585,27 @@ impl Args {
     where
         T: FromStr,
     {
-        match self.pos.get(pos)?.parse::<T>() {
-            Ok(val) => Some(val),
-            Err(_) => None,
-        }
+        self.pos.get(pos)?.parse::<T>().ok()
+    }
+
+    /// gets and parses the positional argument at index into type T, telling apart missing and
+    /// malformed values.
+    pub fn try_at<T>(&self, pos: usize) -> Result<T, ArgError>
+    where
+        T: FromStr,
+        T::Err: Error + Send + Sync + 'static,
+    {
+        let value = self
+            .pos
+            .get(pos)
+            .ok_or_else(|| ArgError::Missing(format!("#{pos}")))?;
+        parse_value(format!("#{pos}"), value)
+    }
+
+    /// gets the positional argument at index parsed by its value parser, without parsing it again.
+    /// returns `None` if there is no argument there or it was parsed into another type.
+    pub fn value_at<T: 'static>(&self, pos: usize) -> Option<&T> {
+        self.typed_pos.get(&pos)?.downcast_ref::<T>()
     }
 
     /// gets the positional argument at index as string reference.
@@ -197,28 +630,60 @@ impl Args {
             .collect()
     }
 
+    /// parses a range of positional arguments into a vector of type T, with descriptive errors.
+    pub fn try_range<T>(&self, range: Range<usize>) -> Result<Vec<T>, ArgError>
+    where
+        T: FromStr,
+        T::Err: Error + Send + Sync + 'static,
+    {
+        let slice = self
+            .pos
+            .get(range.clone())
+            .ok_or_else(|| ArgError::OutOfRange {
+                range: range.clone(),
+                len: self.pos.len(),
+            })?;
+
+        slice
+            .iter()
+            .enumerate()
+            .map(|(i, value)| parse_value(format!("#{}", range.start + i), value))
+            .collect()
+    }
+
+    /// gets the value of a named positional argument.
+    pub fn value_of(&self, name: &str) -> Option<&String> {
+        self.values_of(name)?.first()
+    }
+
+    /// gets every value captured by a named positional argument.
+    pub fn values_of(&self, name: &str) -> Option<&[String]> {
+        let range = self.names.get(name)?;
+        self.pos.get(range.clone())
+    }
+
     /// gets a range of positional arguments as string references.
     pub fn range_string(&self, range: Range<usize>) -> Option<Vec<&String>> {
         self.pos.get(range).map(|slice| slice.iter().collect())
     }
 
     /// returns an iterator over positional arguments.
-    pub fn iter(&self) -> Iter<String> {
+    pub fn iter(&self) -> Iter<'_, String> {
         self.pos.iter()
     }
 
     /// returns an iterator over options.
-    pub fn iter_opt(&self) -> MapIter<String, String> {
+    pub fn iter_opt(&self) -> MapIter<'_, String, String> {
         self.opts.iter()
     }
 
     /// returns a mutable iterator over positional arguments.
-    pub fn iter_mut(&mut self) -> IterMut<String> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, String> {
         self.pos.iter_mut()
     }
 
     /// returns a mutable iterator over options.
-    pub fn iter_mut_opt(&mut self) -> MapIterMut<String, String> {
+    pub fn iter_mut_opt(&mut self) -> MapIterMut<'_, String, String> {
         self.opts.iter_mut()
     }
 
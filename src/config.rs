@@ -0,0 +1,194 @@
+use core::fmt;
+use std::{
+    collections::HashMap,
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+/// a value read from a config file.
+pub struct ConfigValue {
+    /// raw value, with quotes removed.
+    pub value: String,
+    /// line the value was defined on, starting at 1.
+    pub line: usize,
+}
+
+#[derive(Debug, Clone)]
+/// a parsed config file, made of sections keyed by command path.
+pub struct Config {
+    /// path the config was loaded from.
+    pub path: PathBuf,
+    /// map of section names to their keys. the root section has an empty name.
+    pub sections: HashMap<String, HashMap<String, ConfigValue>>,
+}
+
+#[derive(Debug, Clone)]
+/// an error that happened while loading a config file.
+pub struct ConfigError {
+    /// path of the config file.
+    pub path: PathBuf,
+    /// line the error happened on, if any.
+    pub line: Option<usize>,
+    /// description of the error.
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    /// formats a config error.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.path.display(), line, self.message),
+            None => write!(f, "{}: {}", self.path.display(), self.message),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+impl Config {
+    /// parses config text. `path` is only used for error messages and provenance.
+    ///
+    /// the format is a subset of INI and TOML: `key = value` pairs, `[section]` headers where the
+    /// section is a command path joined by dots (`[add.infinite]`), and `#` or `;` comments.
+    /// values can be bare or quoted with `"` or `'`.
+    pub fn parse(path: &Path, text: &str) -> Result<Config, ConfigError> {
+        let mut sections: HashMap<String, HashMap<String, ConfigValue>> = HashMap::new();
+        let mut section = String::new();
+        let error = |line: usize, message: &str| ConfigError {
+            path: path.to_path_buf(),
+            line: Some(line),
+            message: message.to_string(),
+        };
+
+        for (i, raw) in text.lines().enumerate() {
+            let line = i + 1;
+            let trimmed = raw.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with(';') {
+                continue;
+            }
+
+            if let Some(rest) = trimmed.strip_prefix('[') {
+                // parses a section header.
+                let name = rest
+                    .strip_suffix(']')
+                    .ok_or_else(|| error(line, "unterminated section header"))?
+                    .trim();
+                if name.is_empty() || name.split('.').any(|part| part.trim().is_empty()) {
+                    return Err(error(line, "invalid section name"));
+                }
+                section = name
+                    .split('.')
+                    .map(|part| part.trim())
+                    .collect::<Vec<_>>()
+                    .join(".");
+                continue;
+            }
+
+            let (key, value) = trimmed
+                .split_once('=')
+                .ok_or_else(|| error(line, "expected `key = value`"))?;
+            let key = key.trim();
+            if key.is_empty() {
+                return Err(error(line, "missing key before `=`"));
+            }
+            let value = parse_value(value.trim()).map_err(|message| error(line, message))?;
+
+            sections
+                .entry(section.clone())
+                .or_default()
+                .insert(key.to_string(), ConfigValue { value, line });
+        }
+
+        Ok(Config {
+            path: path.to_path_buf(),
+            sections,
+        })
+    }
+
+    /// reads and parses a config file.
+    pub fn load(path: &Path) -> Result<Config, ConfigError> {
+        let text = fs::read_to_string(path).map_err(|error| ConfigError {
+            path: path.to_path_buf(),
+            line: None,
+            message: error.to_string(),
+        })?;
+        Self::parse(path, &text)
+    }
+
+    /// gets a value by section and key.
+    pub fn get(&self, section: &str, key: &str) -> Option<&ConfigValue> {
+        self.sections.get(section)?.get(key)
+    }
+
+    /// lists the paths a config file with the given name is searched in, highest precedence first.
+    ///
+    /// these are the working directory, `$XDG_CONFIG_HOME/<program>` (or `~/.config/<program>`)
+    /// and every `$XDG_CONFIG_DIRS/<program>` (or `/etc/xdg/<program>`).
+    pub fn search_paths(program: &str, file: &str) -> Vec<PathBuf> {
+        let mut paths = vec![PathBuf::from(file)];
+
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+        if let Some(dir) = config_home {
+            paths.push(dir.join(program).join(file));
+        }
+
+        let config_dirs = env::var("XDG_CONFIG_DIRS")
+            .ok()
+            .filter(|dirs| !dirs.is_empty())
+            .unwrap_or_else(|| "/etc/xdg".to_string());
+        for dir in config_dirs.split(':').filter(|dir| !dir.is_empty()) {
+            paths.push(PathBuf::from(dir).join(program).join(file));
+        }
+
+        paths
+    }
+}
+
+/// parses a bare or quoted config value.
+fn parse_value(value: &str) -> Result<String, &'static str> {
+    if let Some(rest) = value.strip_prefix('"') {
+        let mut result = String::new();
+        let mut chars = rest.chars();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '"' => return check_trailing(chars.as_str()).map(|_| result),
+                '\\' => match chars.next() {
+                    Some('n') => result.push('\n'),
+                    Some('t') => result.push('\t'),
+                    Some('\\') => result.push('\\'),
+                    Some('"') => result.push('"'),
+                    _ => return Err("invalid escape sequence"),
+                },
+                _ => result.push(ch),
+            }
+        }
+        Err("unterminated string")
+    } else if let Some(rest) = value.strip_prefix('\'') {
+        let (literal, trailing) = rest.split_once('\'').ok_or("unterminated string")?;
+        check_trailing(trailing).map(|_| literal.to_string())
+    } else {
+        // strips trailing comments from bare values.
+        let end = [" #", " ;", "\t#", "\t;"]
+            .iter()
+            .filter_map(|marker| value.find(marker))
+            .min()
+            .unwrap_or(value.len());
+        Ok(value[..end].trim_end().to_string())
+    }
+}
+
+/// makes sure nothing but a comment follows a quoted value.
+fn check_trailing(trailing: &str) -> Result<(), &'static str> {
+    let trailing = trailing.trim_start();
+    if trailing.is_empty() || trailing.starts_with('#') || trailing.starts_with(';') {
+        Ok(())
+    } else {
+        Err("unexpected text after quoted value")
+    }
+}
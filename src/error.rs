@@ -0,0 +1,154 @@
+use core::fmt;
+use std::error::Error;
+
+use crate::{CommandError, args::Args, layout::display_width};
+
+#[derive(Debug, Clone)]
+/// an error found while parsing a command line, along with the command it happened in.
+pub struct ParseError {
+    /// what went wrong.
+    pub kind: CommandError,
+    /// names of the matched commands, starting with the root.
+    pub path: Vec<String>,
+    /// command line token the error is about, if any.
+    pub token: Option<String>,
+    /// what the token might be a misspelling of.
+    pub suggestions: Vec<String>,
+    /// usage line of the matched command.
+    pub usage: String,
+    /// the command line that was parsed, without the program name.
+    pub line: Vec<String>,
+    /// index in the command line of the offending token, if any.
+    pub index: Option<usize>,
+    /// every error found when errors are collected, each with its own token.
+    pub errors: Vec<ParseError>,
+}
+
+impl ParseError {
+    /// creates a parse error, taking the token and suggestions from its kind and finding the
+    /// offending token in the command line.
+    pub(crate) fn new(kind: CommandError, path: Vec<String>, usage: String, args: &Args) -> Self {
+        let errors = match &kind {
+            CommandError::Multiple(errors) => errors
+                .iter()
+                .map(|error| Self::new(error.clone(), path.clone(), usage.clone(), args))
+                .collect(),
+            _ => Vec::new(),
+        };
+        ParseError {
+            token: kind.token().map(str::to_string),
+            suggestions: kind.suggestion().map(str::to_string).into_iter().collect(),
+            index: Self::find_index(&kind, args),
+            line: args.argv.clone(),
+            kind,
+            path,
+            usage,
+            errors,
+        }
+    }
+
+    /// finds the index in the command line of the token an error is about.
+    fn find_index(kind: &CommandError, args: &Args) -> Option<usize> {
+        match kind {
+            CommandError::UnexpectedArgument { index, .. }
+            | CommandError::UnknownOption { index, .. }
+            | CommandError::UnknownCommand { index, .. } => Some(*index),
+            CommandError::MissingValue(name) | CommandError::Conflict { option: name, .. } => {
+                args.index_of(name)
+            }
+            CommandError::InvalidValue { name, value, .. } => args.index_of(name).or_else(|| {
+                // positional values are found by value, within the argument's range if named.
+                let range = args.names.get(name).cloned().unwrap_or(0..args.pos.len());
+                range
+                    .filter(|pos| args.pos.get(*pos) == Some(value))
+                    .find_map(|pos| args.index_at(pos))
+            }),
+            _ => None,
+        }
+    }
+
+    /// gets the error message without the usage line and hint, starting in lowercase. collected
+    /// errors are listed below it, each with its offending token underlined.
+    pub fn message(&self) -> String {
+        if self.errors.is_empty() {
+            return lowercase_first(&self.kind.to_string());
+        }
+
+        let mut message = format!("found {} errors:", self.errors.len());
+        for error in self.errors.iter() {
+            message.push_str(&format!("\n  {}", error.message()));
+            if let Some(annotated) = error.annotate() {
+                for line in annotated.lines() {
+                    message.push_str(&format!("\n  {line}"));
+                }
+            }
+        }
+
+        message
+    }
+
+    /// reprints the command line with a `^^^` underline under the offending token, or returns
+    /// `None` when the error isn't about a token.
+    pub fn annotate(&self) -> Option<String> {
+        let index = self.index?;
+        let offending = self.line.get(index)?;
+
+        let mut line = self.path[0].clone();
+        let mut column = 0;
+        for (i, token) in self.line.iter().enumerate() {
+            line.push(' ');
+            if i == index {
+                column = display_width(&line);
+            }
+            line.push_str(token);
+        }
+
+        // underlines only the value when it's attached to an option, like `--port=abc`.
+        let (offset, width) = match &self.token {
+            Some(token) if token != offending && offending.ends_with(token.as_str()) => (
+                display_width(offending) - display_width(token),
+                display_width(token),
+            ),
+            _ => (0, display_width(offending)),
+        };
+
+        Some(format!(
+            "    {line}\n    {}{}",
+            " ".repeat(column + offset),
+            "^".repeat(width.max(1))
+        ))
+    }
+
+    /// formats what follows the message: the command line with the offending token underlined,
+    /// the usage line and a hint to see the help screen.
+    pub(crate) fn details(&self) -> String {
+        let annotated = match self.annotate() {
+            Some(annotated) => format!("\n{annotated}\n"),
+            None => String::new(),
+        };
+        format!(
+            "{annotated}\nusage: {}\n\nsee `{} --help` for more information.",
+            self.usage,
+            self.path.join(" ")
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    /// formats a parse error like `error: ...`, followed by the command line with the offending
+    /// token underlined, the usage line and a hint to see the help screen.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error: {}\n{}", self.message(), self.details())
+    }
+}
+
+impl Error for ParseError {}
+
+/// makes the first letter of a message lowercase, like `missing option -a`.
+pub(crate) fn lowercase_first(message: &str) -> String {
+    let mut chars = message.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
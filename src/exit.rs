@@ -0,0 +1,118 @@
+use core::fmt;
+use std::error::Error;
+
+use crate::{CommandError, ParseError};
+
+/// the command ran successfully, or showed help or its version.
+pub const SUCCESS: u8 = 0;
+/// an action failed.
+pub const FAILURE: u8 = 1;
+/// the command line was wrong, like a missing option or an unknown command.
+pub const USAGE: u8 = 2;
+/// input data was wrong (`EX_DATAERR`).
+pub const DATA: u8 = 65;
+/// an input file didn't exist or couldn't be read (`EX_NOINPUT`).
+pub const NO_INPUT: u8 = 66;
+/// a service is unavailable (`EX_UNAVAILABLE`).
+pub const UNAVAILABLE: u8 = 69;
+/// an internal error happened (`EX_SOFTWARE`).
+pub const SOFTWARE: u8 = 70;
+/// an output file couldn't be created (`EX_CANTCREAT`).
+pub const CANT_CREATE: u8 = 73;
+/// reading or writing failed (`EX_IOERR`).
+pub const IO: u8 = 74;
+/// a temporary failure, where retrying might work (`EX_TEMPFAIL`).
+pub const TEMP_FAIL: u8 = 75;
+/// permission was denied (`EX_NOPERM`).
+pub const NO_PERMISSION: u8 = 77;
+/// the configuration was wrong (`EX_CONFIG`).
+pub const CONFIG: u8 = 78;
+
+/// an error that picks its own exit code. actions return it wrapped with
+/// `ExitError::from_status`, as action errors are only checked for an `ExitError`.
+pub trait ExitStatus {
+    /// gets the exit code.
+    fn exit_code(&self) -> u8;
+}
+
+impl ExitStatus for CommandError {
+    /// gets the exit code, which is `USAGE` for command line errors and `CONFIG` for config errors.
+    fn exit_code(&self) -> u8 {
+        match self {
+            Self::Config(_) => CONFIG,
+            _ => USAGE,
+        }
+    }
+}
+
+impl ExitStatus for ParseError {
+    /// gets the exit code of the error kind.
+    fn exit_code(&self) -> u8 {
+        self.kind.exit_code()
+    }
+}
+
+#[derive(Debug)]
+/// an action error with the exit code the program ends with.
+pub struct ExitError {
+    /// exit code.
+    pub code: u8,
+    /// error that caused the exit.
+    pub error: Box<dyn Error + Send + Sync>,
+}
+
+impl ExitError {
+    /// creates an error with an exit code.
+    pub fn new<E: Into<Box<dyn Error + Send + Sync>>>(code: u8, error: E) -> ExitError {
+        ExitError {
+            code,
+            error: error.into(),
+        }
+    }
+
+    /// creates an error from any error that picks its own exit code.
+    pub fn from_status<E: ExitStatus + Error + Send + Sync + 'static>(error: E) -> ExitError {
+        Self::new(error.exit_code(), error)
+    }
+}
+
+impl ExitStatus for ExitError {
+    /// gets the exit code.
+    fn exit_code(&self) -> u8 {
+        self.code
+    }
+}
+
+impl fmt::Display for ExitError {
+    /// formats the error that caused the exit.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl Error for ExitError {
+    /// gets the error that caused the exit.
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.error.as_ref())
+    }
+}
+
+/// gets the exit code for an error returned from running a command, looking for an `ExitError`
+/// in the error and its sources. errors that don't pick a code exit with `FAILURE`.
+pub(crate) fn code_of(error: &(dyn Error + Send + Sync + 'static)) -> u8 {
+    if let Some(error) = error.downcast_ref::<CommandError>() {
+        return error.exit_code();
+    } else if let Some(error) = error.downcast_ref::<ParseError>() {
+        return error.exit_code();
+    }
+
+    let mut next: Option<&(dyn Error + 'static)> = Some(error);
+    while let Some(error) = next {
+        if let Some(error) = error.downcast_ref::<ExitError>() {
+            return error.exit_code();
+        }
+        next = error.source();
+    }
+
+    FAILURE
+}
@@ -0,0 +1,127 @@
+use std::env;
+
+/// width used when the terminal width isn't known.
+const DEFAULT_WIDTH: usize = 80;
+/// spaces before every row of a table.
+const INDENT: usize = 2;
+/// spaces between the columns of a table.
+const GAP: usize = 2;
+/// widest the first column gets before descriptions move to their own line.
+const MAX_NAME_WIDTH: usize = 30;
+
+/// gets the terminal width from `COLUMNS`, or 80 if it isn't set.
+pub(crate) fn terminal_width() -> usize {
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.trim().parse().ok())
+        .filter(|columns| *columns > 0)
+        .unwrap_or(DEFAULT_WIDTH)
+}
+
+/// gets how many terminal columns a character takes: 0 for control and combining characters, 2
+/// for wide characters like CJK and emoji, and 1 for the rest.
+fn char_width(ch: char) -> usize {
+    let code = ch as u32;
+    match code {
+        0..=0x1F | 0x7F..=0x9F => 0,
+        0x0300..=0x036F
+        | 0x0483..=0x0489
+        | 0x0591..=0x05BD
+        | 0x0610..=0x061A
+        | 0x064B..=0x065F
+        | 0x1AB0..=0x1AFF
+        | 0x1DC0..=0x1DFF
+        | 0x200B..=0x200F
+        | 0x20D0..=0x20FF
+        | 0xFE00..=0xFE0F
+        | 0xFE20..=0xFE2F => 0,
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+/// gets how many terminal columns a string takes.
+pub(crate) fn display_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+/// wraps text into lines that fit a width, breaking between words. words wider than the width get
+/// a line of their own.
+pub(crate) fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && display_width(&line) + 1 + display_width(word) > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// lays rows of names and descriptions out as two aligned columns that fit a width, styling names
+/// with `paint`. names too wide for the first column get their description on the next line.
+pub(crate) fn table(
+    rows: &[(String, String)],
+    width: usize,
+    paint: impl Fn(&str) -> String,
+) -> String {
+    let name_width = rows
+        .iter()
+        .map(|(name, _)| display_width(name))
+        .filter(|name_width| *name_width <= MAX_NAME_WIDTH)
+        .max()
+        .unwrap_or(0);
+    let desc_column = INDENT + name_width + GAP;
+    // descriptions always get some room, even on narrow terminals.
+    let desc_width = width.saturating_sub(desc_column).max(20);
+
+    let mut builder = String::new();
+    for (name, desc) in rows {
+        builder.push_str(&" ".repeat(INDENT));
+        builder.push_str(&paint(name));
+
+        let mut lines = wrap(desc, desc_width).into_iter();
+        let first = lines.next().unwrap_or_default();
+        let name_width = display_width(name);
+        // leaves no trailing spaces after names without a description.
+        if !first.is_empty() {
+            if name_width <= MAX_NAME_WIDTH {
+                builder.push_str(&" ".repeat(desc_column - INDENT - name_width));
+            } else {
+                builder.push('\n');
+                builder.push_str(&" ".repeat(desc_column));
+            }
+            builder.push_str(&first);
+        }
+        builder.push('\n');
+
+        for line in lines {
+            builder.push_str(&" ".repeat(desc_column));
+            builder.push_str(&line);
+            builder.push('\n');
+        }
+    }
+
+    builder
+}
@@ -1,11 +1,48 @@
 mod args;
+mod config;
+mod error;
+pub mod exit;
+mod layout;
+mod matches;
+mod output;
+mod spec;
+mod style;
+mod suggest;
 #[cfg(test)]
 mod tests;
+mod usage;
+mod value;
 
 use core::fmt;
-use std::{env, error::Error, str::FromStr};
+use std::{env, error::Error, ops::Range, path::PathBuf, process::ExitCode};
 
-use args::Args;
+pub use args::{ArgError, Args, ValueSource};
+pub use config::{Config, ConfigError, ConfigValue};
+pub use error::ParseError;
+pub use exit::{ExitError, ExitStatus};
+pub use matches::Matches;
+pub use output::{Buffer, Output};
+use spec::TemplatePart;
+pub use spec::{SpecError, ValueName};
+pub use style::{Color, ColorChoice, Style, Theme};
+pub use value::{Validator, ValueParser};
+
+/// gets the version of the crate it's used in, from `CARGO_PKG_VERSION`, for `Command::version`.
+#[macro_export]
+macro_rules! version {
+    () => {
+        env!("CARGO_PKG_VERSION")
+    };
+}
+
+/// function run when a command is executed.
+type Action = Box<dyn Fn(Args) -> Result<(), Box<dyn Error + Send + Sync>>>;
+/// function run to show a help screen.
+type Help = Box<dyn Fn(HelpReason, &Command, Args)>;
+/// function reading environment variables.
+type EnvSource = Box<dyn Fn(&str) -> Option<String>>;
+/// title of a help section and its rows of names and descriptions.
+type Section = (String, Vec<(String, String)>);
 
 #[derive(Debug, Clone)]
 /// reasons for a help screen to be triggered.
@@ -15,27 +52,283 @@ pub enum HelpReason {
     /// command lacks an action.
     MissingAction,
     /// required option missing from arguments.
-    MissingOption(CLIOption),
-    /// required positional argument missing, given start and end indexes.
-    MissingArgument(usize, usize),
+    MissingOption(Box<CLIOption>),
+    /// required positional argument missing from arguments.
+    MissingArgument {
+        /// usage form of the argument, like `<dest>`.
+        name: String,
+    },
+    /// option that takes a value was given without one.
+    MissingValue(String),
+    /// a positional argument that captures multiple values got too few of them.
+    ArgumentCount {
+        /// usage form of the argument, like `<src>...`.
+        name: String,
+        /// minimum number of values.
+        min: usize,
+        /// maximum number of values, if any.
+        max: Option<usize>,
+        /// number of values given.
+        got: usize,
+    },
+    /// an option or argument value was rejected by a validator.
+    InvalidValue {
+        /// name of the option or argument.
+        name: String,
+        /// value that was rejected.
+        value: String,
+        /// why the value was rejected.
+        reason: String,
+    },
+    /// more positional arguments were given than the command takes.
+    UnexpectedArgument {
+        /// index of the first extra argument.
+        index: usize,
+        /// value of the first extra argument.
+        value: String,
+        /// subcommand the value might be a misspelling of.
+        suggestion: Option<String>,
+    },
+    /// an option no matched command has was given.
+    UnknownOption {
+        /// index of the option in the command line.
+        index: usize,
+        /// name of the option.
+        name: String,
+        /// option the name might be a misspelling of.
+        suggestion: Option<String>,
+    },
+    /// a subcommand that doesn't exist was given.
+    UnknownCommand {
+        /// index of the subcommand in the command line.
+        index: usize,
+        /// name of the subcommand.
+        name: String,
+        /// subcommand the name might be a misspelling of.
+        suggestion: Option<String>,
+    },
+    /// two options that can't be used together were given.
+    Conflict {
+        /// option that was given.
+        option: String,
+        /// option it conflicts with.
+        other: String,
+    },
+    /// several of the other reasons, when every error is collected.
+    Multiple(Vec<HelpReason>),
+}
+
+impl HelpReason {
+    /// gets the help reason for an error, if it should show a help screen.
+    fn from_error(error: &CommandError) -> Option<HelpReason> {
+        Some(match error.clone() {
+            CommandError::MissingOption(option) => HelpReason::MissingOption(option),
+            CommandError::MissingArgument { name } => HelpReason::MissingArgument { name },
+            CommandError::MissingValue(name) => HelpReason::MissingValue(name),
+            CommandError::ArgumentCount {
+                name,
+                min,
+                max,
+                got,
+            } => HelpReason::ArgumentCount {
+                name,
+                min,
+                max,
+                got,
+            },
+            CommandError::InvalidValue {
+                name,
+                value,
+                reason,
+            } => HelpReason::InvalidValue {
+                name,
+                value,
+                reason,
+            },
+            CommandError::UnexpectedArgument {
+                index,
+                value,
+                suggestion,
+            } => HelpReason::UnexpectedArgument {
+                index,
+                value,
+                suggestion,
+            },
+            CommandError::UnknownOption {
+                index,
+                name,
+                suggestion,
+            } => HelpReason::UnknownOption {
+                index,
+                name,
+                suggestion,
+            },
+            CommandError::UnknownCommand {
+                index,
+                name,
+                suggestion,
+            } => HelpReason::UnknownCommand {
+                index,
+                name,
+                suggestion,
+            },
+            CommandError::Conflict { option, other } => HelpReason::Conflict { option, other },
+            CommandError::Multiple(errors) => {
+                HelpReason::Multiple(errors.iter().filter_map(Self::from_error).collect())
+            }
+            CommandError::Config(_) => return None,
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
 /// reasons that running a command might've failed. this is different from `HelpReason` because it's the return type of the running `command`.
 pub enum CommandError {
     /// required option is missing from arguments.
-    MissingOption(CLIOption),
-    /// required positional argument, given start and end indexes.
-    MissingArgument(usize, usize),
+    MissingOption(Box<CLIOption>),
+    /// required positional argument missing from arguments.
+    MissingArgument {
+        /// usage form of the argument, like `<dest>`.
+        name: String,
+    },
+    /// option that takes a value was given without one.
+    MissingValue(String),
+    /// a positional argument that captures multiple values got too few of them.
+    ArgumentCount {
+        /// usage form of the argument, like `<src>...`.
+        name: String,
+        /// minimum number of values.
+        min: usize,
+        /// maximum number of values, if any.
+        max: Option<usize>,
+        /// number of values given.
+        got: usize,
+    },
+    /// a config file couldn't be read or parsed.
+    Config(ConfigError),
+    /// an option or argument value was rejected by a validator.
+    InvalidValue {
+        /// name of the option or argument.
+        name: String,
+        /// value that was rejected.
+        value: String,
+        /// why the value was rejected.
+        reason: String,
+    },
+    /// more positional arguments were given than the command takes.
+    UnexpectedArgument {
+        /// index of the first extra argument.
+        index: usize,
+        /// value of the first extra argument.
+        value: String,
+        /// subcommand the value might be a misspelling of.
+        suggestion: Option<String>,
+    },
+    /// an option no matched command has was given.
+    UnknownOption {
+        /// index of the option in the command line.
+        index: usize,
+        /// name of the option.
+        name: String,
+        /// option the name might be a misspelling of.
+        suggestion: Option<String>,
+    },
+    /// a subcommand that doesn't exist was given.
+    UnknownCommand {
+        /// index of the subcommand in the command line.
+        index: usize,
+        /// name of the subcommand.
+        name: String,
+        /// subcommand the name might be a misspelling of.
+        suggestion: Option<String>,
+    },
+    /// two options that can't be used together were given.
+    Conflict {
+        /// option that was given.
+        option: String,
+        /// option it conflicts with.
+        other: String,
+    },
+    /// several errors, when every error is collected.
+    Multiple(Vec<CommandError>),
+}
+
+impl CommandError {
+    /// gets the command line token the error is about, if any.
+    pub fn token(&self) -> Option<&str> {
+        match self {
+            Self::MissingValue(name)
+            | Self::UnknownOption { name, .. }
+            | Self::UnknownCommand { name, .. } => Some(name),
+            Self::InvalidValue { value, .. } | Self::UnexpectedArgument { value, .. } => {
+                Some(value)
+            }
+            Self::Conflict { option, .. } => Some(option),
+            _ => None,
+        }
+    }
+
+    /// gets the suggested replacement for the offending token, if any.
+    pub fn suggestion(&self) -> Option<&str> {
+        match self {
+            Self::UnexpectedArgument { suggestion, .. }
+            | Self::UnknownOption { suggestion, .. }
+            | Self::UnknownCommand { suggestion, .. } => suggestion.as_deref(),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for CommandError {
     /// formats a command.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::MissingOption(option) => write!(f, "Missing option: {:#?}", option),
-            Self::MissingArgument(start, end) => {
-                write!(f, "Missing arguments from {start} to {end}")
+            Self::MissingOption(option) => {
+                write!(f, "Missing option {}", option.names.join(" or "))
+            }
+            Self::MissingArgument { name } => write!(f, "Missing argument {name}"),
+            Self::MissingValue(name) => write!(f, "Missing value for option {name}"),
+            Self::ArgumentCount {
+                name,
+                min,
+                max,
+                got,
+            } => write!(f, "Expected {} {name}, got {got}", count_range(*min, *max)),
+            Self::Config(error) => write!(f, "Invalid config: {error}"),
+            Self::InvalidValue {
+                name,
+                value,
+                reason,
+            } => write!(f, "Invalid value {value:?} for {name}: {reason}"),
+            Self::UnexpectedArgument {
+                index,
+                value,
+                suggestion,
+            } => {
+                write!(f, "Unexpected argument {value:?} at position {index}")?;
+                write_suggestion(f, suggestion)
+            }
+            Self::UnknownOption {
+                name, suggestion, ..
+            } => {
+                write!(f, "Unknown option {name}")?;
+                write_suggestion(f, suggestion)
+            }
+            Self::UnknownCommand {
+                name, suggestion, ..
+            } => {
+                write!(f, "Unknown command {name:?}")?;
+                write_suggestion(f, suggestion)
+            }
+            Self::Conflict { option, other } => {
+                write!(f, "Option {option} can't be used with {other}")
+            }
+            Self::Multiple(errors) => {
+                write!(f, "Found {} errors:", errors.len())?;
+                for error in errors {
+                    write!(f, "\n  {}", error::lowercase_first(&error.to_string()))?;
+                }
+                Ok(())
             }
         }
     }
@@ -43,6 +336,24 @@ impl fmt::Display for CommandError {
 
 impl Error for CommandError {}
 
+/// writes a "did you mean" hint if there is a suggestion.
+fn write_suggestion(f: &mut fmt::Formatter<'_>, suggestion: &Option<String>) -> fmt::Result {
+    match suggestion {
+        Some(suggestion) => write!(f, ", did you mean {suggestion:?}?"),
+        None => Ok(()),
+    }
+}
+
+/// describes how many values an argument takes, like "at least 1" or "between 1 and 3".
+fn count_range(min: usize, max: Option<usize>) -> String {
+    match max {
+        Some(max) if max == min => format!("exactly {min}"),
+        Some(max) if min == 0 => format!("at most {max}"),
+        Some(max) => format!("between {min} and {max}"),
+        None => format!("at least {min}"),
+    }
+}
+
 #[derive(Debug, Clone)]
 /// a command line option (--example, -e).
 pub struct CLIOption {
@@ -52,17 +363,358 @@ pub struct CLIOption {
     pub desc: String,
     /// whether this option is required.
     pub required: bool,
+    /// placeholder for the value the option takes, if it takes one.
+    pub value: Option<ValueName>,
+    /// value used when the option isn't given anywhere else.
+    pub default: Option<String>,
+    /// environment variable the option can be read from.
+    pub env: Option<String>,
+    /// checks run on the value before the action.
+    pub validators: Vec<Validator>,
+    /// type the value is parsed into before the action.
+    pub parser: Option<ValueParser>,
+    /// names of options that can't be given along with this one.
+    pub conflicts: Vec<String>,
+    /// heading of the help section the option is listed under, if not `options`.
+    pub heading: Option<String>,
+    /// position of the option in help, if not its insertion order.
+    pub order: Option<usize>,
+    /// whether the option is left out of help. it still works when given.
+    pub hidden: bool,
+    /// message shown in a warning when the option is given, like `use --output instead`.
+    pub deprecated: Option<String>,
+    /// name of the option this one is an alias of, which gets its value when it's given.
+    pub alias_of: Option<String>,
+}
+
+impl CLIOption {
+    /// creates a required option from a spec and description. the spec has the names and,
+    /// optionally, a placeholder for its value: `-p, --port <number>` takes a value, and
+    /// `--color [when]` takes an optional one.
+    ///
+    /// panics if the spec is malformed, see `CLIOption::parse` to handle the error instead.
+    pub fn new(spec: &str, desc: &str) -> CLIOption {
+        Self::parse(spec, desc).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// creates a required option from a spec and description, failing if the spec is malformed.
+    pub fn parse(spec: &str, desc: &str) -> Result<CLIOption, SpecError> {
+        let (names, value) = spec::parse_option(spec)?;
+        Ok(CLIOption {
+            names,
+            desc: desc.to_string(),
+            required: true,
+            value,
+            default: None,
+            env: None,
+            validators: Vec::new(),
+            parser: None,
+            conflicts: Vec::new(),
+            heading: None,
+            order: None,
+            hidden: false,
+            deprecated: None,
+            alias_of: None,
+        })
+    }
+
+    /// sets whether the option is required.
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// sets the default value of the option.
+    pub fn default(mut self, value: &str) -> Self {
+        self.default = Some(value.to_string());
+        self
+    }
+
+    /// sets the environment variable the option can be read from.
+    pub fn env(mut self, var: &str) -> Self {
+        self.env = Some(var.to_string());
+        self
+    }
+
+    /// adds a check run on the value before the action, failing with the given message.
+    pub fn validator<T: Fn(&str) -> bool + Send + Sync + 'static>(
+        mut self,
+        check: T,
+        message: &str,
+    ) -> Self {
+        self.validators.push(Validator::new(check, message));
+        self
+    }
+
+    /// sets the type the value is parsed into before the action. parsed values can be read with
+    /// `Args::value`.
+    pub fn parser(mut self, parser: ValueParser) -> Self {
+        self.parser = Some(parser);
+        self
+    }
+
+    /// makes the command fail when this option and another one, by any of its names, are both
+    /// given on the command line.
+    pub fn conflicts_with(mut self, name: &str) -> Self {
+        self.conflicts.push(name.to_string());
+        self
+    }
+
+    /// lists the option in help under its own heading, like `Network options`.
+    pub fn heading(mut self, heading: &str) -> Self {
+        self.heading = Some(heading.to_string());
+        self
+    }
+
+    /// sets the position of the option in help. options with a position come first, from
+    /// lowest to highest, followed by the rest in the order they were added.
+    pub fn order(mut self, order: usize) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    /// sets whether the option is left out of help and suggestions. it still works when given.
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// marks the option as deprecated, warning with a message like `use --output instead`
+    /// whenever it's given.
+    pub fn deprecated(mut self, message: &str) -> Self {
+        self.deprecated = Some(message.to_string());
+        self
+    }
+
+    /// makes the option an alias of another one by any of its names, so giving either fills
+    /// the value of both. useful to keep an old spelling working, along with `deprecated`.
+    pub fn alias_of(mut self, name: &str) -> Self {
+        self.alias_of = Some(name.to_string());
+        self
+    }
+
+    /// gets the usage form of the option, like `-p <number>`.
+    fn usage(&self) -> String {
+        match &self.value {
+            Some(ValueName {
+                name,
+                required: true,
+            }) => format!("{} <{name}>", self.names[0]),
+            Some(ValueName { name, .. }) => format!("{} [{name}]", self.names[0]),
+            None => self.names[0].clone(),
+        }
+    }
+
+    /// names used to look the option up in config files, which are its names without dashes.
+    fn config_keys(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(|name| name.trim_start_matches('-'))
+    }
 }
 
 #[derive(Debug, Clone)]
 /// a command line positional argument.
 pub struct CLIArgument {
+    /// name used to look the argument up and to show it in help, if any.
+    pub name: Option<String>,
     /// short description of the argument.
     pub desc: String,
     /// whether this argument is required.
     pub required: bool,
     /// whether this argument captures multiple values.
     pub array: bool,
+    /// minimum number of values an argument capturing multiple values takes. required arguments
+    /// always take at least one.
+    pub min: usize,
+    /// maximum number of values an argument capturing multiple values takes, if any.
+    pub max: Option<usize>,
+    /// checks run on every value before the action.
+    pub validators: Vec<Validator>,
+    /// type every value is parsed into before the action.
+    pub parser: Option<ValueParser>,
+    /// heading of the help section the argument is listed under, if not `arguments`.
+    pub heading: Option<String>,
+    /// position of the argument in help, if not its position on the command line.
+    pub order: Option<usize>,
+}
+
+impl CLIArgument {
+    /// creates a required positional argument with description.
+    pub fn new(desc: &str) -> CLIArgument {
+        CLIArgument {
+            name: None,
+            desc: desc.to_string(),
+            required: true,
+            array: false,
+            min: 0,
+            max: None,
+            validators: Vec::new(),
+            parser: None,
+            heading: None,
+            order: None,
+        }
+    }
+
+    /// creates a named positional argument. `<name>` is required, `[name]` is optional, and a
+    /// trailing `...` (`<name>...` or `<name...>`) captures multiple values.
+    ///
+    /// panics if the spec is malformed, see `CLIArgument::parse` to handle the error instead.
+    pub fn named(spec: &str, desc: &str) -> CLIArgument {
+        Self::parse(spec, desc).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// creates a named positional argument from a spec, failing if the spec is malformed.
+    pub fn parse(spec: &str, desc: &str) -> Result<CLIArgument, SpecError> {
+        let spec = spec::parse_argument(spec)?;
+        Ok(CLIArgument {
+            name: Some(spec.name),
+            ..CLIArgument::new(desc)
+                .required(spec.required)
+                .array(spec.array)
+        })
+    }
+
+    /// sets whether the argument is required.
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// sets whether the argument captures multiple values.
+    pub fn array(mut self, array: bool) -> Self {
+        self.array = array;
+        self
+    }
+
+    /// sets the minimum number of values, for arguments capturing multiple values.
+    pub fn min(mut self, min: usize) -> Self {
+        self.min = min;
+        self
+    }
+
+    /// sets the maximum number of values, for arguments capturing multiple values.
+    pub fn max(mut self, max: usize) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// adds a check run on every value before the action, failing with the given message.
+    pub fn validator<T: Fn(&str) -> bool + Send + Sync + 'static>(
+        mut self,
+        check: T,
+        message: &str,
+    ) -> Self {
+        self.validators.push(Validator::new(check, message));
+        self
+    }
+
+    /// sets the type every value is parsed into before the action. parsed values can be read with
+    /// `Args::value_at`.
+    pub fn parser(mut self, parser: ValueParser) -> Self {
+        self.parser = Some(parser);
+        self
+    }
+
+    /// lists the argument in help under its own heading.
+    pub fn heading(mut self, heading: &str) -> Self {
+        self.heading = Some(heading.to_string());
+        self
+    }
+
+    /// sets the position of the argument in help. arguments with a position come first, from
+    /// lowest to highest, followed by the rest in command line order.
+    pub fn order(mut self, order: usize) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    /// minimum number of values the argument takes.
+    fn min_count(&self) -> usize {
+        if self.array {
+            self.min.max(self.required as usize)
+        } else {
+            self.required as usize
+        }
+    }
+
+    /// maximum number of values the argument takes, if any.
+    fn max_count(&self) -> Option<usize> {
+        if self.array { self.max } else { Some(1) }
+    }
+
+    /// label of the argument at a position, used in help and errors: its name, or `#pos`.
+    fn label(&self, pos: usize) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("#{pos}"),
+        }
+    }
+
+    /// usage form of the argument, like `<name>`, `[name]` or `<name>...`.
+    fn usage(&self, pos: usize) -> String {
+        let label = self.label(pos);
+        match (self.required, self.array) {
+            (true, false) => format!("<{label}>"),
+            (true, true) => format!("<{label}>..."),
+            (false, false) => format!("[{label}]"),
+            (false, true) => format!("[{label}...]"),
+        }
+    }
+}
+
+/// how built-in output is shown for the matched command.
+struct Screen {
+    /// where output is written to.
+    output: Output,
+    /// names of the commands above the matched one, which usage lines start with.
+    parents: String,
+    /// width help is wrapped to.
+    width: usize,
+    /// when to color output.
+    color: ColorChoice,
+    /// styles used when color is on.
+    theme: Theme,
+    /// settings help is rendered with.
+    page: Page,
+}
+
+/// settings a help screen is rendered with, which subcommands inherit.
+struct Page {
+    /// version shown for the `{version}` placeholder.
+    version: Option<String>,
+    /// template help is laid out with, if not the default layout.
+    template: Option<Vec<TemplatePart>>,
+    /// whether the `help` subcommand is listed with the subcommands.
+    help_command: bool,
+}
+
+impl Screen {
+    /// gets the theme for output to a stream, which is plain when color is off.
+    fn theme(&self, terminal: bool) -> Theme {
+        if self.color.enabled(terminal) {
+            self.theme.clone()
+        } else {
+            Theme::plain()
+        }
+    }
+}
+
+/// arguments parsed and checked against the matched command.
+struct Checked<'a> {
+    /// command the arguments matched.
+    command: &'a Command,
+    /// parsed arguments.
+    args: Args,
+    /// closest help function to the matched command.
+    help: Option<&'a Help>,
+    /// whether the user asked for help, in which case nothing was checked.
+    help_asked: bool,
+    /// version text to print if the user asked for the version, in which case nothing was
+    /// checked.
+    version: Option<String>,
+    /// result of checking the arguments.
+    result: Result<(), CommandError>,
+    /// warnings about deprecated commands and options that were used.
+    warnings: Vec<String>,
 }
 
 /// represents a cli command.
@@ -70,17 +722,67 @@ pub struct Command {
     /// all aliases for the command.
     names: Vec<String>,
     /// function run when the command is executed.
-    action: Option<Box<dyn Fn(Args) -> Result<(), Box<dyn Error + Send + Sync>>>>,
+    action: Option<Action>,
     /// function run to show help screen.
-    help: Option<Box<dyn Fn(HelpReason, &Command, Args)>>,
+    help: Option<Help>,
     /// optional short description of the command.
     desc: Option<String>,
+    /// one-line description shown in the parent's list of subcommands, instead of `desc`.
+    summary: Option<String>,
+    /// long description shown on the command's own help page.
+    about: Option<String>,
+    /// example command lines and what they do, shown at the end of help.
+    examples: Vec<(String, String)>,
+    /// text shown before the rest of help.
+    before_help: Option<String>,
+    /// text shown after the rest of help.
+    after_help: Option<String>,
     /// subcommands of this command.
     children: Vec<Command>,
     /// options available to this command.
     options: Vec<CLIOption>,
     /// positional arguments for this command.
     arguments: Vec<CLIArgument>,
+    /// file name searched for in the config search paths.
+    config_file: Option<String>,
+    /// names of the option that sets the config file path.
+    config_flag: Vec<String>,
+    /// function environment variables are read with, if not inherited.
+    env_source: Option<EnvSource>,
+    /// whether positional arguments past the declared ones are allowed.
+    allow_extra_args: bool,
+    /// whether every error is collected instead of stopping at the first.
+    collect_errors: bool,
+    /// where built-in output is written to, if not inherited.
+    output: Option<Output>,
+    /// names of the flags that show help, if not inherited.
+    help_flags: Option<Vec<String>>,
+    /// whether commands with subcommands get a `help` subcommand, if not inherited.
+    help_command: Option<bool>,
+    /// version printed by the version flags, if not inherited.
+    version: Option<String>,
+    /// build metadata printed by the long version flags, like the git commit.
+    version_metadata: Vec<(String, String)>,
+    /// names of the flags that print the version, if not inherited.
+    version_flags: Option<Vec<String>>,
+    /// width help is wrapped to, if not inherited.
+    help_width: Option<usize>,
+    /// template help is laid out with, if not inherited.
+    help_template: Option<Vec<TemplatePart>>,
+    /// styles used for help, errors and warnings when color is on, if not inherited.
+    theme: Option<Theme>,
+    /// when to color output, if not inherited.
+    color: Option<ColorChoice>,
+    /// names of the option that sets when to color output.
+    color_flag: Vec<String>,
+    /// category the command is listed under in its parent's help, if not `commands`.
+    category: Option<String>,
+    /// position of the command in its parent's help, if not its insertion order.
+    order: Option<usize>,
+    /// whether the command is left out of its parent's help.
+    hidden: bool,
+    /// message shown in a warning when the command is used, if it's deprecated.
+    deprecated: Option<String>,
 }
 
 impl Command {
@@ -89,11 +791,36 @@ impl Command {
         Command {
             names: vec![name.to_string()],
             desc: None,
+            summary: None,
+            about: None,
+            examples: Vec::new(),
+            before_help: None,
+            after_help: None,
             children: Vec::new(),
             options: Vec::new(),
             arguments: Vec::new(),
             action: None,
             help: None,
+            config_file: None,
+            config_flag: Vec::new(),
+            env_source: None,
+            allow_extra_args: false,
+            collect_errors: false,
+            output: None,
+            help_flags: None,
+            help_command: None,
+            version: None,
+            version_metadata: Vec::new(),
+            version_flags: None,
+            help_width: None,
+            help_template: None,
+            theme: None,
+            color: None,
+            color_flag: Vec::new(),
+            category: None,
+            order: None,
+            hidden: false,
+            deprecated: None,
         }
     }
 
@@ -107,10 +834,7 @@ impl Command {
     }
 
     /// sets the help action for the command.
-    pub fn help<T: Fn(HelpReason, &Command, Args) -> () + 'static>(
-        &mut self,
-        action: T,
-    ) -> &mut Self {
+    pub fn help<T: Fn(HelpReason, &Command, Args) + 'static>(&mut self, action: T) -> &mut Self {
         self.help = Some(Box::new(action));
         self
     }
@@ -121,6 +845,66 @@ impl Command {
         self
     }
 
+    /// sets the one-line description shown in the parent's list of subcommands, instead of
+    /// `desc`.
+    pub fn summary(&mut self, summary: &str) -> &mut Self {
+        self.summary = Some(summary.to_string());
+        self
+    }
+
+    /// sets the long description shown at the top of the command's own help page. lines are
+    /// wrapped to the help width, and blank lines are kept.
+    pub fn about(&mut self, about: &str) -> &mut Self {
+        self.about = Some(about.to_string());
+        self
+    }
+
+    /// adds an example command line and what it does, shown at the end of help.
+    pub fn example(&mut self, command_line: &str, explanation: &str) -> &mut Self {
+        self.examples
+            .push((command_line.to_string(), explanation.to_string()));
+        self
+    }
+
+    /// sets text shown as is before the rest of help.
+    pub fn before_help(&mut self, text: &str) -> &mut Self {
+        self.before_help = Some(text.to_string());
+        self
+    }
+
+    /// sets text shown as is after the rest of help.
+    pub fn after_help(&mut self, text: &str) -> &mut Self {
+        self.after_help = Some(text.to_string());
+        self
+    }
+
+    /// lists the command in its parent's help under a category, like `Remote commands`.
+    pub fn category(&mut self, category: &str) -> &mut Self {
+        self.category = Some(category.to_string());
+        self
+    }
+
+    /// sets the position of the command in its parent's help. commands with a position come
+    /// first, from lowest to highest, followed by the rest in the order they were added.
+    pub fn order(&mut self, order: usize) -> &mut Self {
+        self.order = Some(order);
+        self
+    }
+
+    /// sets whether the command is left out of its parent's help and suggestions. it still runs
+    /// when used.
+    pub fn hidden(&mut self, hidden: bool) -> &mut Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// marks the command as deprecated, warning with a message like `use fetch instead` whenever
+    /// it's used.
+    pub fn deprecated(&mut self, message: &str) -> &mut Self {
+        self.deprecated = Some(message.to_string());
+        self
+    }
+
     /// adds an alias to the command.
     pub fn alias(&mut self, alias: &str) -> &mut Self {
         self.names.push(alias.to_string());
@@ -129,55 +913,216 @@ impl Command {
 
     /// adds a required option with names and description.
     pub fn option(&mut self, names: &str, desc: &str) -> &mut Self {
-        let split = names.split(",");
+        self.options.push(CLIOption::new(names, desc));
+        self
+    }
 
-        self.options.push(CLIOption {
-            names: split.map(|a| a.trim().to_string()).collect(),
-            desc: desc.to_string(),
-            required: true,
-        });
+    /// adds a fully configured option.
+    pub fn add_option(&mut self, option: CLIOption) -> &mut Self {
+        self.options.push(option);
         self
     }
 
-    /// adds a required positional argument with description.
-    pub fn argument(&mut self, desc: &str) -> &mut Self {
-        self.arguments.push(CLIArgument {
-            desc: desc.to_string(),
-            required: true,
-            array: false,
-        });
+    /// adds a named positional argument with description. `<name>` is required, `[name]` is
+    /// optional, and a trailing `...` captures multiple values. values can be read with
+    /// `Args::value_of` and `Args::values_of`.
+    pub fn argument(&mut self, name: &str, desc: &str) -> &mut Self {
+        self.arguments.push(CLIArgument::named(name, desc));
         self
     }
 
     /// adds a positional argument that captures multiple values.
     pub fn array_argument(&mut self, desc: &str) -> &mut Self {
-        self.arguments.push(CLIArgument {
-            desc: desc.to_string(),
-            required: false,
-            array: true,
-        });
+        self.arguments
+            .push(CLIArgument::new(desc).required(false).array(true));
+        self
+    }
+
+    /// adds a fully configured positional argument.
+    pub fn add_argument(&mut self, argument: CLIArgument) -> &mut Self {
+        self.arguments.push(argument);
         self
     }
 
     /// adds an optional option with names and description.
     pub fn opt_option(&mut self, names: &str, desc: &str) -> &mut Self {
-        let split = names.split(",");
+        self.options
+            .push(CLIOption::new(names, desc).required(false));
+        self
+    }
 
-        self.options.push(CLIOption {
-            names: split.map(|a| a.trim().to_string()).collect(),
-            desc: desc.to_string(),
-            required: false,
+    /// loads option values from config files with the given name, searched for in the working
+    /// directory and the XDG config directories. sections are keyed by command path, like
+    /// `[add.infinite]`, and keys are option names without dashes. subcommands inherit the file
+    /// unless they set their own.
+    pub fn config(&mut self, file: &str) -> &mut Self {
+        self.config_file = Some(file.to_string());
+        self
+    }
+
+    /// adds an optional option that sets the path of the config file, skipping the search. the
+    /// option takes a `<path>` value unless its spec names another placeholder. when several
+    /// matched commands have one, the closest one given wins.
+    pub fn config_option(&mut self, spec: &str) -> &mut Self {
+        let mut option = CLIOption::new(spec, "Path to a config file.").required(false);
+        option.value.get_or_insert(ValueName {
+            name: "path".to_string(),
+            required: true,
         });
+        self.config_flag = option.names.clone();
+        self.options.push(option);
+        self
+    }
+
+    /// reads the environment variables of options with a function instead of from the process
+    /// environment, like a map of variables in tests. applies to subcommands too, unless they set
+    /// their own.
+    pub fn env_source<T: Fn(&str) -> Option<String> + 'static>(&mut self, source: T) -> &mut Self {
+        self.env_source = Some(Box::new(source));
         self
     }
 
     /// adds an optional positional argument.
     pub fn opt_argument(&mut self, desc: &str) -> &mut Self {
-        self.arguments.push(CLIArgument {
-            desc: desc.to_string(),
-            required: false,
-            array: false,
+        self.arguments.push(CLIArgument::new(desc).required(false));
+        self
+    }
+
+    /// sets whether positional arguments past the declared ones are allowed. when they aren't,
+    /// which is the default, extra arguments make the command fail.
+    pub fn allow_extra_args(&mut self, allow: bool) -> &mut Self {
+        self.allow_extra_args = allow;
+        self
+    }
+
+    /// sets whether every missing option, missing argument and invalid value is reported at once
+    /// as `CommandError::Multiple`, instead of stopping at the first. applies to subcommands too.
+    pub fn collect_errors(&mut self, collect: bool) -> &mut Self {
+        self.collect_errors = collect;
+        self
+    }
+
+    /// sets where help screens, errors and warnings are written to, instead of stdout and stderr.
+    /// applies to subcommands too, unless they configure their own.
+    pub fn configure_output(&mut self, output: Output) -> &mut Self {
+        self.output = Some(output);
+        self
+    }
+
+    /// sets the flags that show help from a spec like `-h, --help`, instead of `-h` and `--help`.
+    /// applies to subcommands too, unless they set their own. options with the same names take
+    /// precedence over the flags.
+    ///
+    /// panics if the spec is malformed.
+    pub fn help_flags(&mut self, spec: &str) -> &mut Self {
+        let (names, _) = spec::parse_option(spec).unwrap_or_else(|error| panic!("{error}"));
+        self.help_flags = Some(names);
+        self
+    }
+
+    /// removes the flags that show help, for this command and its subcommands.
+    pub fn disable_help_flags(&mut self) -> &mut Self {
+        self.help_flags = Some(Vec::new());
+        self
+    }
+
+    /// sets whether `help [command...]` shows help for a subcommand, like `app help add`. it's
+    /// on by default and applies to subcommands too, unless they set their own.
+    pub fn help_command(&mut self, enabled: bool) -> &mut Self {
+        self.help_command = Some(enabled);
+        self
+    }
+
+    /// sets the version printed by `-V` and `--version`, like `1.2.3`, as `name version`. the
+    /// `version!()` macro gets the version of the crate it's used in. subcommands inherit the
+    /// version, unless they set their own.
+    pub fn version(&mut self, version: &str) -> &mut Self {
+        self.version = Some(version.to_string());
+        self
+    }
+
+    /// adds build metadata printed under the version by `--version`, like
+    /// `.version_metadata("commit", env!("GIT_HASH"))`.
+    pub fn version_metadata(&mut self, key: &str, value: &str) -> &mut Self {
+        self.version_metadata
+            .push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// adds the platform the program was built for as `target` version metadata, like
+    /// `x86_64-linux`.
+    pub fn target_metadata(&mut self) -> &mut Self {
+        let target = format!("{}-{}", env::consts::ARCH, env::consts::OS);
+        self.version_metadata("target", &target)
+    }
+
+    /// sets the flags that print the version from a spec like `-V, --version`. flags starting
+    /// with `--` print the long form. applies to subcommands too, unless they set their own.
+    ///
+    /// panics if the spec is malformed.
+    pub fn version_flags(&mut self, spec: &str) -> &mut Self {
+        let (names, _) = spec::parse_option(spec).unwrap_or_else(|error| panic!("{error}"));
+        self.version_flags = Some(names);
+        self
+    }
+
+    /// sets the width help is wrapped to, instead of the `COLUMNS` environment variable or 80.
+    /// applies to subcommands too, unless they set their own.
+    pub fn help_width(&mut self, width: usize) -> &mut Self {
+        self.help_width = Some(width);
+        self
+    }
+
+    /// lays help out with a template instead of the default layout, like
+    /// `{name} {version}\n{about}\n\nUSAGE:\n{usage}\n\n{options}`. the placeholders are `name`,
+    /// `version`, `desc`, `before_help`, `about`, `usage`, `arguments`, `options`, `commands`,
+    /// `examples` and `after_help`, and `{{` and `}}` stand for literal braces. applies to
+    /// subcommands too, unless they set their own.
+    ///
+    /// panics if the template has an unknown placeholder or unmatched braces, see
+    /// `Command::try_help_template` to handle the error instead.
+    pub fn help_template(&mut self, template: &str) -> &mut Self {
+        self.try_help_template(template)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// lays help out with a template like `help_template`, returning an error if the template has
+    /// an unknown placeholder or unmatched braces.
+    pub fn try_help_template(&mut self, template: &str) -> Result<&mut Self, SpecError> {
+        self.help_template = Some(spec::parse_template(template)?);
+        Ok(self)
+    }
+
+    /// sets the styles used for help, errors and warnings when color is on. applies to
+    /// subcommands too, unless they set their own.
+    pub fn theme(&mut self, theme: Theme) -> &mut Self {
+        self.theme = Some(theme);
+        self
+    }
+
+    /// sets when to color output. the default, `ColorChoice::Auto`, colors output written to a
+    /// terminal unless `NO_COLOR` is set, or always if `CLICOLOR_FORCE` is. applies to
+    /// subcommands too, unless they set their own.
+    pub fn color(&mut self, color: ColorChoice) -> &mut Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// adds an optional option that sets when to color output, taking `auto`, `always` or
+    /// `never`, like `--color <when>`. it takes precedence over `Command::color`.
+    pub fn color_option(&mut self, spec: &str) -> &mut Self {
+        let mut option = CLIOption::new(spec, "When to color output: auto, always or never.")
+            .required(false)
+            .validator(
+                |value| value.parse::<ColorChoice>().is_ok(),
+                "expected auto, always or never",
+            );
+        option.value.get_or_insert(ValueName {
+            name: "when".to_string(),
+            required: true,
         });
+        self.color_flag = option.names.clone();
+        self.options.push(option);
         self
     }
 
@@ -187,78 +1132,554 @@ impl Command {
         self
     }
 
-    /// creates and adds a new subcommand by name.
-    pub fn command(&mut self, name: &str) -> &mut Command {
-        let command = Command::new(name);
+    /// creates a command from a spec with its name and arguments, like `clone <source> [dest]`,
+    /// failing if the spec is malformed.
+    pub fn from_spec(spec: &str) -> Result<Command, SpecError> {
+        let (name, arguments) = spec::parse_command(spec)?;
+        let mut command = Command::new(&name);
+        for argument in arguments {
+            command.arguments.push(CLIArgument {
+                name: Some(argument.name),
+                ..CLIArgument::new("")
+                    .required(argument.required)
+                    .array(argument.array)
+            });
+        }
+
+        Ok(command)
+    }
+
+    /// builds a command tree from a docopt-like usage text, so the documentation is the parser
+    /// definition. the text before `Usage:` becomes the description, and every usage line adds
+    /// its subcommands, options and arguments:
+    ///
+    /// ```text
+    /// Greet people and add numbers.
+    ///
+    /// Usage:
+    ///   human greet <names>...
+    ///   human add -x=<n> -y=<n> [--verbose]
+    ///   human add infinite [<numbers>...]
+    ///
+    /// Options:
+    ///   -x=<n>, --x=<n>  First number.
+    ///   -v, --verbose    Print more [default: false].
+    /// ```
+    ///
+    /// options in brackets are optional, and `[options]` allows every option of the options
+    /// section. options that aren't in any usage line are added to the root command.
+    pub fn from_usage(text: &str) -> Result<Command, SpecError> {
+        usage::parse_usage(text)
+    }
+
+    /// finds a subcommand by its path of names or aliases separated by spaces, like
+    /// `add infinite`.
+    pub fn find_command(&mut self, path: &str) -> Option<&mut Command> {
+        let mut command = self;
+        for name in path.split_whitespace() {
+            command = command
+                .children
+                .iter_mut()
+                .find(|child| child.names.iter().any(|alias| alias == name))?;
+        }
+
+        Some(command)
+    }
+
+    /// creates and adds a new subcommand from a spec, which is its name optionally followed by
+    /// arguments, like `clone <source> [dest]` or `add <files...>`.
+    ///
+    /// panics if the spec is malformed, see `Command::from_spec` to handle the error instead.
+    pub fn command(&mut self, spec: &str) -> &mut Command {
+        let command = Command::from_spec(spec).unwrap_or_else(|error| panic!("{error}"));
         self.children.push(command);
         self.children.last_mut().unwrap()
     }
 
     /// runs the command with given argument strings.
     pub fn run(&self, args: Vec<String>) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
-        let (command, args, help_option) = Args::parse(self, args);
-        if args.has("--help") {
-            let reason = HelpReason::MissingAction;
-            match help_option {
-                Some(help) => help(reason, command, args),
-                None => command.default_help(reason),
+        self.execute(args).1
+    }
+
+    /// runs the command with given argument strings, also getting how built-in output is shown
+    /// for the matched command.
+    fn execute(
+        &self,
+        args: Vec<String>,
+    ) -> (Screen, Result<(), Box<dyn Error + Send + Sync + 'static>>) {
+        let Checked {
+            command,
+            args,
+            help,
+            help_asked,
+            version,
+            result,
+            warnings,
+        } = self.check(args);
+        let screen = self.screen(Some(&args));
+        let show_help = |reason, args| match help {
+            Some(help) => help(reason, command, args),
+            None => command.default_help(&screen),
+        };
+
+        if help_asked {
+            show_help(HelpReason::UserAsked, args);
+            return (screen, Ok(()));
+        }
+        if let Some(version) = version {
+            screen.output.write_out(&format!("{version}\n"));
+            return (screen, Ok(()));
+        }
+
+        let theme = screen.theme(screen.output.err_terminal());
+        for warning in warnings {
+            let prefix = theme.warning.paint("warning:");
+            screen.output.warning(&format!("{prefix} {warning}"));
+        }
+
+        if let Err(error) = result {
+            if let Some(reason) = HelpReason::from_error(&error) {
+                match help {
+                    Some(help) => help(reason, command, args),
+                    None => Self::show_error(&self.parse_error(error.clone(), &args), &screen),
+                }
             }
-            return Ok(());
+            return (screen, Err(Box::new(error)));
         }
 
-        // check for required options
-        for option in command.options.iter() {
-            if !option.required {
+        let result = match &command.action {
+            Some(action) => action(args),
+            None => {
+                show_help(HelpReason::MissingAction, args);
+                Ok(())
+            }
+        };
+        (screen, result)
+    }
+
+    /// parses the command line and checks it without printing or running anything, returning the
+    /// matched commands and their arguments.
+    // parse errors carry the command line to underline the offending token in it.
+    #[allow(clippy::result_large_err)]
+    pub fn try_parse(&self, args: Vec<String>) -> Result<Matches, ParseError> {
+        let Checked {
+            args,
+            help_asked,
+            version,
+            result,
+            warnings,
+            ..
+        } = self.check(args);
+        let path = self.path_names(&args.path);
+
+        if let Err(kind) = result {
+            return Err(self.parse_error(kind, &args));
+        }
+
+        Ok(Matches {
+            levels: (0..path.len()).map(|depth| args.level(depth)).collect(),
+            path,
+            args,
+            help_asked,
+            version_asked: version.is_some() && !help_asked,
+            warnings,
+        })
+    }
+
+    /// creates a parse error for an error found in the command that arguments matched.
+    fn parse_error(&self, kind: CommandError, args: &Args) -> ParseError {
+        let path = self.path_names(&args.path);
+        let lineage = self.lineage(&args.path);
+        let command = lineage[lineage.len() - 1];
+        let usage = command
+            .generate_usages(&Self::parents(&path))
+            .join("\n       ");
+        ParseError::new(kind, path, usage, args)
+    }
+
+    /// parses the command line from argument string slices without running anything.
+    #[allow(clippy::result_large_err)]
+    pub fn try_parse_str(&self, args: Vec<&str>) -> Result<Matches, ParseError> {
+        self.try_parse(args.iter().map(|arg| arg.to_string()).collect())
+    }
+
+    /// parses arguments and checks them against the matched command. when the user asks for help
+    /// nothing is checked.
+    fn check(&self, args: Vec<String>) -> Checked<'_> {
+        let (command, mut args, help) = Args::parse(self, args);
+        let help_flags = Command::help_flags_of(&self.lineage(&args.path));
+        let help_asked = args.help_index.is_some()
+            || help_flags
+                .iter()
+                .any(|name| matches!(args.source(name), Some(ValueSource::CommandLine(_))));
+
+        // the help subcommand only takes subcommands.
+        let extra = args.help_index.and_then(|help_index| {
+            (0..args.pos.len()).find(|pos| args.pos_index[*pos] > help_index)
+        });
+        // the version flags only work when a version is set.
+        let version = self.version_text(&args);
+        let mut warnings = Vec::new();
+
+        let result = if let Some(pos) = extra {
+            let name = args.pos[pos].clone();
+            Err(CommandError::UnknownCommand {
+                index: args.pos_index[pos],
+                suggestion: suggest::suggest(&name, command.visible_names()),
+                name,
+            })
+        } else if help_asked || version.is_some() {
+            Ok(())
+        } else {
+            self.check_args(command, &mut args, &mut warnings)
+        };
+        let help_asked = help_asked && result.is_ok();
+
+        Checked {
+            command,
+            args,
+            help,
+            help_asked,
+            version,
+            result,
+            warnings,
+        }
+    }
+
+    /// fills in missing option values, then checks options and arguments against the matched
+    /// command and parses their typed values. uses of deprecated commands and options are added
+    /// to the warnings.
+    fn check_args(
+        &self,
+        command: &Command,
+        args: &mut Args,
+        warnings: &mut Vec<String>,
+    ) -> Result<(), CommandError> {
+        let lineage = self.lineage(&args.path);
+        let collect = lineage.iter().any(|command| command.collect_errors);
+        let mut errors = args.errors.clone();
+        // stops at the first error found, unless every error should be collected.
+        let stop = |errors: &mut Vec<CommandError>| {
+            if collect || errors.is_empty() {
+                Ok(())
+            } else {
+                Err(errors.remove(0))
+            }
+        };
+        stop(&mut errors)?;
+
+        let find_option = |name: &str| {
+            lineage
+                .iter()
+                .flat_map(|command| command.options.iter())
+                .find(|option| option.names.iter().any(|n| n == name))
+        };
+        let mut given: Vec<(usize, &String)> = args
+            .sources
+            .iter()
+            .filter_map(|(name, source)| match source {
+                ValueSource::CommandLine(index) => Some((*index, name)),
+                _ => None,
+            })
+            .collect();
+        given.sort();
+
+        // check for options no matched command has
+        let mut builtin_flags = Command::help_flags_of(&lineage);
+        if lineage.iter().any(|command| command.version.is_some()) {
+            builtin_flags.extend(Command::version_flags_of(&lineage));
+        }
+        for (index, name) in given.iter() {
+            if builtin_flags.contains(name) || find_option(name).is_some() {
                 continue;
             }
 
-            let mut found = false;
-            for name in option.names.iter() {
-                if args.has(name) {
-                    found = true;
-                    break;
+            let candidates = lineage
+                .iter()
+                .flat_map(|command| command.options.iter())
+                .filter(|option| !option.hidden)
+                .flat_map(|option| option.names.iter())
+                .chain(builtin_flags.iter())
+                .map(|name| name.as_str());
+            errors.push(CommandError::UnknownOption {
+                index: *index,
+                name: name.to_string(),
+                suggestion: suggest::suggest(name, candidates),
+            });
+        }
+        stop(&mut errors)?;
+
+        // check for options given along with options they conflict with
+        for (_, name) in given.iter() {
+            let Some(option) = find_option(name) else {
+                continue;
+            };
+            for other in option.conflicts.iter() {
+                let names = find_option(other)
+                    .map_or(vec![other], |other| other.names.iter().collect::<Vec<_>>());
+                if let Some((_, other)) = given.iter().find(|(_, given)| names.contains(given)) {
+                    errors.push(CommandError::Conflict {
+                        option: name.to_string(),
+                        other: other.to_string(),
+                    });
                 }
             }
+        }
+        stop(&mut errors)?;
 
-            if !found {
-                let reason = HelpReason::MissingOption(option.clone());
-                match help_option {
-                    Some(help) => help(reason, command, args),
-                    None => command.default_help(reason),
+        // warn about deprecated commands and options, and fill in the options aliases stand for
+        let mut copies = Vec::new();
+        for (command, name) in lineage.iter().zip(args.commands.iter()) {
+            if let Some(message) = &command.deprecated {
+                warnings.push(format!("command {name} is deprecated, {message}"));
+            }
+        }
+        for (_, name) in given.iter() {
+            let Some(option) = find_option(name) else {
+                continue;
+            };
+            if let Some(message) = &option.deprecated {
+                warnings.push(format!("{name} is deprecated, {message}"));
+            }
+            if let Some(canonical) = option.alias_of.as_deref().and_then(find_option) {
+                for other in canonical.names.iter().filter(|other| !args.has(other)) {
+                    copies.push((name.to_string(), other.clone()));
                 }
-                return Err(Box::new(CommandError::MissingOption(option.clone())));
             }
         }
+        for (name, other) in copies {
+            args.copy(&name, &other);
+        }
 
-        // check for required arguments
-        for (pos, arg) in command.arguments.iter().enumerate() {
-            if arg.required && !args.has_at(pos) {
-                let end = if arg.array {
-                    command.arguments.len()
-                } else {
-                    pos
-                };
-                let reason = HelpReason::MissingArgument(pos, end);
-                match help_option {
-                    Some(help) => help(reason, command, args),
-                    None => command.default_help(reason),
-                }
+        self.resolve(args)?;
 
-                return Err(Box::new(CommandError::MissingArgument(pos, end)));
+        // check for required options
+        for option in command.options.iter() {
+            if option.required && !option.names.iter().any(|name| args.has(name)) {
+                errors.push(CommandError::MissingOption(Box::new(option.clone())));
             }
         }
+        stop(&mut errors)?;
 
-        match &command.action {
-            Some(action) => action(args),
-            None => {
-                let reason = HelpReason::MissingAction;
-                match help_option {
-                    Some(help) => help(reason, command, args),
-                    None => command.default_help(reason),
+        // check for required arguments
+        let first = args.first_pos();
+        let ranges = command.ranges_of(args);
+        let taken = ranges.last().map_or(first, |range| range.end);
+        for (pos, (arg, range)) in command.arguments.iter().zip(ranges).enumerate() {
+            let min = arg.min_count();
+            if range.len() >= min {
+                continue;
+            }
+
+            errors.push(if arg.array {
+                CommandError::ArgumentCount {
+                    name: arg.usage(pos),
+                    min,
+                    max: arg.max_count(),
+                    got: range.len(),
                 }
-                Ok(())
+            } else {
+                CommandError::MissingArgument {
+                    name: arg.usage(pos),
+                }
+            });
+        }
+        stop(&mut errors)?;
+
+        // check for extra arguments
+        if !command.allow_extra_args && taken < args.pos.len() {
+            let value = args.pos[taken].clone();
+            let index = args.pos_index[taken];
+            let suggestion = suggest::suggest(&value, command.visible_names());
+
+            // a command with subcommands and no arguments of its own expected a subcommand.
+            errors.push(if taken == first && !command.children.is_empty() {
+                CommandError::UnknownCommand {
+                    index,
+                    name: value,
+                    suggestion,
+                }
+            } else {
+                CommandError::UnexpectedArgument {
+                    index,
+                    value,
+                    suggestion,
+                }
+            });
+        }
+        stop(&mut errors)?;
+
+        // parse typed values and check them against validators
+        command.validate(&lineage, args, &mut errors);
+
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ if collect => Err(CommandError::Multiple(errors)),
+            _ => Err(errors.remove(0)),
+        }
+    }
+
+    /// gets the commands on a path of subcommand indexes, starting with this one.
+    fn lineage(&self, path: &[usize]) -> Vec<&Command> {
+        let mut command = self;
+        let mut lineage = vec![command];
+        for index in path {
+            command = &command.children[*index];
+            lineage.push(command);
+        }
+
+        lineage
+    }
+
+    /// gets the help flags of the closest command in a lineage that set them, leaving out names
+    /// that options already use. the default is `-h` and `--help`.
+    pub(crate) fn help_flags_of(lineage: &[&Command]) -> Vec<String> {
+        Self::flags_of(lineage, |command| &command.help_flags, ["-h", "--help"])
+    }
+
+    /// gets the version flags of the closest command in a lineage that set them, leaving out
+    /// names that options already use. the default is `-V` and `--version`.
+    pub(crate) fn version_flags_of(lineage: &[&Command]) -> Vec<String> {
+        Self::flags_of(
+            lineage,
+            |command| &command.version_flags,
+            ["-V", "--version"],
+        )
+    }
+
+    /// gets built-in flags from the closest command in a lineage that set them, leaving out names
+    /// that options already use.
+    fn flags_of(
+        lineage: &[&Command],
+        flags: impl Fn(&Command) -> &Option<Vec<String>>,
+        default: [&str; 2],
+    ) -> Vec<String> {
+        let flags = lineage
+            .iter()
+            .rev()
+            .find_map(|command| flags(command).clone())
+            .unwrap_or_else(|| default.map(str::to_string).to_vec());
+
+        flags
+            .into_iter()
+            .filter(|flag| {
+                !lineage
+                    .iter()
+                    .flat_map(|command| command.options.iter())
+                    .any(|option| option.names.contains(flag))
+            })
+            .collect()
+    }
+
+    /// gets the version text to print if a version flag was given and a matched command has a
+    /// version. the flags starting with `--` print the long form, with build metadata.
+    fn version_text(&self, args: &Args) -> Option<String> {
+        let lineage = self.lineage(&args.path);
+        let given = Self::version_flags_of(&lineage)
+            .into_iter()
+            .filter(|name| matches!(args.source(name), Some(ValueSource::CommandLine(_))))
+            .collect::<Vec<_>>();
+        if given.is_empty() {
+            return None;
+        }
+
+        let command = lineage
+            .iter()
+            .rev()
+            .find(|command| command.version.is_some())?;
+        let mut text = format!(
+            "{} {}",
+            self.path_names(&args.path).join(" "),
+            command.version.as_deref().unwrap_or_default()
+        );
+        if given.iter().any(|name| name.starts_with("--")) {
+            for (key, value) in command.version_metadata.iter() {
+                text.push_str(&format!("\n{key}: {value}"));
             }
         }
+
+        Some(text)
+    }
+
+    /// gets whether the closest command in a lineage that set it has the `help` subcommand, which
+    /// is on by default.
+    pub(crate) fn help_command_of(lineage: &[&Command]) -> bool {
+        lineage
+            .iter()
+            .rev()
+            .find_map(|command| command.help_command)
+            .unwrap_or(true)
+    }
+
+    /// gets how built-in output is shown for the command the arguments matched, or for this one
+    /// when there are no arguments. settings come from the closest command that has them.
+    fn screen(&self, args: Option<&Args>) -> Screen {
+        let path = args.map_or(&[][..], |args| &args.path);
+        let lineage = self.lineage(path);
+        let closest =
+            |setting: fn(&Command) -> Option<_>| lineage.iter().rev().find_map(|c| setting(c));
+
+        let flag = args.and_then(|args| {
+            lineage.iter().rev().find_map(|command| {
+                command
+                    .color_flag
+                    .iter()
+                    .find_map(|name| args.get_string(name)?.parse().ok())
+            })
+        });
+
+        Screen {
+            output: self.output_for(path),
+            parents: Self::parents(&self.path_names(path)),
+            width: closest(|command| command.help_width).unwrap_or_else(layout::terminal_width),
+            color: flag
+                .or_else(|| lineage.iter().rev().find_map(|command| command.color))
+                .unwrap_or_default(),
+            theme: lineage
+                .iter()
+                .rev()
+                .find_map(|command| command.theme.clone())
+                .unwrap_or_default(),
+            page: Page {
+                version: lineage
+                    .iter()
+                    .rev()
+                    .find_map(|command| command.version.clone()),
+                template: lineage
+                    .iter()
+                    .rev()
+                    .find_map(|command| command.help_template.clone()),
+                help_command: Self::help_command_of(&lineage),
+            },
+        }
+    }
+
+    /// gets the output of the closest command on a path of subcommand indexes that configured
+    /// one, or stdout and stderr if none did.
+    fn output_for(&self, path: &[usize]) -> Output {
+        self.lineage(path)
+            .iter()
+            .rev()
+            .find_map(|command| command.output.clone())
+            .unwrap_or_default()
+    }
+
+    /// joins the names of the commands above the last one on a path into a usage prefix, like
+    /// `human add `.
+    fn parents(path: &[String]) -> String {
+        path[..path.len() - 1]
+            .iter()
+            .map(|name| format!("{name} "))
+            .collect()
+    }
+
+    /// gets the names of the commands on a path of subcommand indexes, starting with this one.
+    fn path_names(&self, path: &[usize]) -> Vec<String> {
+        self.lineage(path)
+            .iter()
+            .map(|command| command.names[0].clone())
+            .collect()
     }
 
     /// runs the command with argument string slices.
@@ -271,119 +1692,606 @@ impl Command {
         self.run(env::args().skip(1).collect())
     }
 
-    /// default help function called on help reasons.
-    fn default_help(&self, reason: HelpReason) {
-        match &reason {
-            HelpReason::MissingAction | HelpReason::UserAsked => {
-                println!("{}", self.generate_help());
+    /// runs the command using environment arguments and gets the exit code to end the program
+    /// with, so `main` can be `fn main() -> ExitCode { program.main() }`.
+    ///
+    /// showing help exits with `exit::SUCCESS` and command line errors with `exit::USAGE`. action
+    /// errors exit with `exit::FAILURE`, unless they are or wrap an `ExitError` picking another
+    /// code.
+    /// errors the help screen didn't already show are printed to stderr.
+    pub fn main(&self) -> ExitCode {
+        ExitCode::from(self.exit_code(env::args().skip(1).collect()))
+    }
+
+    /// runs the command, prints an error from running it if it wasn't shown yet to the output of
+    /// the matched command and gets its exit code.
+    fn exit_code(&self, args: Vec<String>) -> u8 {
+        let (screen, result) = self.execute(args);
+        let Err(error) = result else {
+            return exit::SUCCESS;
+        };
+
+        let shown = error
+            .downcast_ref::<CommandError>()
+            .is_some_and(|error| HelpReason::from_error(error).is_some());
+        if !shown {
+            let theme = screen.theme(screen.output.err_terminal());
+            screen
+                .output
+                .error(&format!("{} {error}", theme.error.paint("error:")));
+        }
+
+        exit::code_of(error.as_ref())
+    }
+
+    /// fills options missing from the command line, looking at the environment, then config files,
+    /// then defaults.
+    fn resolve(&self, args: &mut Args) -> Result<(), CommandError> {
+        let configs = self.load_configs(args)?;
+        let env_source = self
+            .lineage(&args.path)
+            .into_iter()
+            .rev()
+            .find_map(|command| command.env_source.as_ref());
+
+        let mut command = self;
+        let mut section = Vec::new();
+        for depth in 0..=args.path.len() {
+            if depth > 0 {
+                command = &command.children[args.path[depth - 1]];
+                section.push(command.names[0].as_str());
             }
-            HelpReason::MissingArgument(start, end) => {
-                eprintln!(
-                    "missing argument from positions {} to {}!",
-                    start + 1,
-                    end + 1
-                );
-                eprintln!("{}", self.generate_help());
+            let section = section.join(".");
+
+            for option in command.options.iter() {
+                if option.names.iter().any(|name| args.has(name)) {
+                    continue;
+                }
+
+                let from_env = option.env.as_ref().and_then(|var| {
+                    let value = match env_source {
+                        Some(source) => source(var),
+                        None => env::var(var).ok(),
+                    };
+                    value.map(|value| (value, ValueSource::Env(var.clone())))
+                });
+                let from_config = || {
+                    configs.iter().find_map(|config| {
+                        option
+                            .config_keys()
+                            .find_map(|key| config.get(&section, key))
+                            .map(|value| {
+                                let source = ValueSource::Config(config.path.clone(), value.line);
+                                (value.value.clone(), source)
+                            })
+                    })
+                };
+                let from_default = || {
+                    option
+                        .default
+                        .clone()
+                        .map(|value| (value, ValueSource::Default))
+                };
+
+                if let Some((value, source)) = from_env.or_else(from_config).or_else(from_default) {
+                    // flags that take no value are left off when set to false. `0` isn't, since
+                    // options without a placeholder can still be given numbers like `-x=0`.
+                    if option.value.is_none() && value.trim().eq_ignore_ascii_case("false") {
+                        continue;
+                    }
+                    for name in option.names.iter() {
+                        args.set(name, &value, source.clone(), depth);
+                    }
+                }
             }
-            HelpReason::MissingOption(option) => {
-                eprintln!("missing option {}!", option.names.join(" or "));
-                eprintln!("{}", self.generate_help())
+        }
+
+        Ok(())
+    }
+
+    /// loads the config given with the config option, or every config found in the search paths.
+    /// both come from the closest matched command that has them.
+    fn load_configs(&self, args: &Args) -> Result<Vec<Config>, CommandError> {
+        let lineage = self.lineage(&args.path);
+        if let Some(path) = lineage.iter().rev().find_map(|command| {
+            command
+                .config_flag
+                .iter()
+                .find_map(|name| args.get_string(name))
+        }) {
+            return Config::load(&PathBuf::from(path))
+                .map(|config| vec![config])
+                .map_err(CommandError::Config);
+        }
+
+        let mut configs = Vec::new();
+        if let Some(file) = lineage
+            .iter()
+            .rev()
+            .find_map(|command| command.config_file.as_ref())
+        {
+            for path in Config::search_paths(&self.names[0], file) {
+                if path.is_file() {
+                    configs.push(Config::load(&path).map_err(CommandError::Config)?);
+                }
             }
         }
+
+        Ok(configs)
+    }
+
+    /// parses every option of the matched command and its parents, and every argument, that has
+    /// a value with its value parser, then runs its validators, adding every rejected value to
+    /// the errors.
+    fn validate(&self, lineage: &[&Command], args: &mut Args, errors: &mut Vec<CommandError>) {
+        // options of parent commands can be given to subcommands too.
+        for option in lineage.iter().flat_map(|command| command.options.iter()) {
+            let mut parsed = None;
+            for name in option.names.iter() {
+                let Some(value) = args.opts.get(name) else {
+                    continue;
+                };
+                let invalid = |reason| CommandError::InvalidValue {
+                    name: name.clone(),
+                    value: value.clone(),
+                    reason,
+                };
+
+                if let Some(parser) = &option.parser {
+                    match parser.parse(value) {
+                        Ok(typed) => {
+                            parsed.get_or_insert(typed.clone());
+                            args.typed.insert(name.clone(), typed);
+                        }
+                        Err(reason) => {
+                            errors.push(invalid(reason));
+                            continue;
+                        }
+                    }
+                }
+                if let Some(Err(reason)) = option
+                    .validators
+                    .iter()
+                    .map(|validator| validator.validate(value))
+                    .find(Result::is_err)
+                {
+                    errors.push(invalid(reason));
+                }
+            }
+
+            // makes the parsed value available under every name of the option.
+            if let Some(typed) = parsed {
+                for name in option.names.iter() {
+                    if !args.typed.contains_key(name) {
+                        args.typed.insert(name.clone(), typed.clone());
+                    }
+                }
+            }
+        }
+
+        let ranges = self.ranges_of(args);
+        for (pos, (arg, range)) in self.arguments.iter().zip(ranges).enumerate() {
+            for index in range {
+                let value = &args.pos[index];
+                let invalid = |reason| CommandError::InvalidValue {
+                    name: arg.label(pos),
+                    value: value.clone(),
+                    reason,
+                };
+
+                if let Some(parser) = &arg.parser {
+                    match parser.parse(value) {
+                        Ok(typed) => {
+                            args.typed_pos.insert(index, typed);
+                        }
+                        Err(reason) => {
+                            errors.push(invalid(reason));
+                            continue;
+                        }
+                    }
+                }
+                if let Some(Err(reason)) = arg
+                    .validators
+                    .iter()
+                    .map(|validator| validator.validate(value))
+                    .find(Result::is_err)
+                {
+                    errors.push(invalid(reason));
+                }
+            }
+        }
+    }
+
+    /// splits a number of positional values between the arguments, returning the range of values
+    /// each argument gets. fixed arguments are reserved first, even after one capturing multiple
+    /// values like in `cp <src>... <dest>`, followed by the minimum of arguments capturing
+    /// multiple values. the values left are then given out from left to right. values that don't
+    /// fit any argument are left out of every range.
+    pub(crate) fn ranges(&self, count: usize) -> Vec<Range<usize>> {
+        let mut left = count;
+        let mut counts = vec![0; self.arguments.len()];
+        for array in [false, true] {
+            for (arg, taken) in self.arguments.iter().zip(counts.iter_mut()) {
+                if arg.array == array {
+                    *taken = arg.min_count().min(left);
+                    left -= *taken;
+                }
+            }
+        }
+
+        for (arg, taken) in self.arguments.iter().zip(counts.iter_mut()) {
+            let room = match arg.max_count() {
+                Some(max) => max.saturating_sub(*taken),
+                None => left,
+            };
+            let extra = room.min(left);
+            *taken += extra;
+            left -= extra;
+        }
+
+        let mut start = 0;
+        counts
+            .into_iter()
+            .map(|taken| {
+                let range = start..start + taken;
+                start += taken;
+                range
+            })
+            .collect()
+    }
+
+    /// splits the positional values given to the matched command between its arguments like
+    /// `ranges`, leaving out values given to its parents. ranges are indexes into all the values.
+    pub(crate) fn ranges_of(&self, args: &Args) -> Vec<Range<usize>> {
+        let first = args.first_pos();
+        self.ranges(args.pos.len() - first)
+            .into_iter()
+            .map(|range| range.start + first..range.end + first)
+            .collect()
+    }
+
+    /// default help function, called when the user asks for help or the command lacks an action.
+    /// errors are shown with `show_error` instead.
+    fn default_help(&self, screen: &Screen) {
+        let output = &screen.output;
+        let theme = screen.theme(output.out_terminal());
+        let help = self.render_help(&screen.parents, screen.width, &theme, &screen.page);
+        output.write_out(&format!("{help}\n"));
+    }
+
+    /// shows an error found in the command line, with the offending token underlined, the usage
+    /// line and a hint to see the help screen.
+    fn show_error(error: &ParseError, screen: &Screen) {
+        let output = &screen.output;
+        let theme = screen.theme(output.err_terminal());
+        output.styled_error(&format!("error: {}", error.message()), &theme.error);
+        output.write_err(&format!("{}\n", error.details()));
     }
 
     /// generates a help screen string.
     pub fn generate_help(&self) -> String {
-        let mut builder = String::new();
-        builder.push_str(&format!("usage:{}\n", self.generate_usage(" ")));
-        builder.push_str(&format!("arguments:\n{}", self.generate_args("\t", "\n")));
-        builder.push_str(&format!("options:\n{}", self.generate_opts("\t", "\n")));
-        builder.push_str(&format!(
-            "commands:\n{}",
-            self.generate_sub_commands("\t", "\n")
-        ));
-        builder
+        self.generate_help_with_prefix("")
+    }
+
+    /// generates a help screen string, with usage lines starting with a prefix like the names of
+    /// the parent commands (`human add `). `Args::command_path` has the names of the matched
+    /// commands.
+    pub fn generate_help_with_prefix(&self, prefix: &str) -> String {
+        let width = self.help_width.unwrap_or_else(layout::terminal_width);
+        let page = Page {
+            version: self.version.clone(),
+            template: self.help_template.clone(),
+            help_command: Self::help_command_of(&[self]),
+        };
+        self.render_help(prefix, width, &Theme::plain(), &page)
+    }
+
+    /// renders a help screen that fits a width, with the arguments, options and subcommands as
+    /// aligned columns. empty sections are left out.
+    fn render_help(&self, prefix: &str, width: usize, theme: &Theme, page: &Page) -> String {
+        let parts = self.help_parts(prefix, width, theme, page);
+        let part = |name: &str| {
+            parts
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map_or("", |(_, part)| part.as_str())
+        };
+        if let Some(template) = &page.template {
+            return template
+                .iter()
+                .map(|piece| match piece {
+                    TemplatePart::Text(text) => text.as_str(),
+                    TemplatePart::Placeholder(name) => part(name),
+                })
+                .collect();
+        }
+
+        // parts that are empty are left out, along with their line.
+        let lines = |names: &[&str]| -> String {
+            names
+                .iter()
+                .map(|name| part(name))
+                .filter(|part| !part.is_empty())
+                .map(|part| format!("{part}\n"))
+                .collect()
+        };
+        format!(
+            "{}{} {}\n{}",
+            lines(&["before_help", "about"]),
+            theme.heading.paint("usage:"),
+            self.generate_usages(prefix).join("\n       "),
+            lines(&["arguments", "options", "commands", "examples", "after_help"])
+        )
     }
 
-    /// generates a usage string with a prefix.
+    /// renders every part of a help screen a template can use, by placeholder name. parts have
+    /// no trailing newline, and are empty when the command has nothing to show for them.
+    fn help_parts(
+        &self,
+        prefix: &str,
+        width: usize,
+        theme: &Theme,
+        page: &Page,
+    ) -> Vec<(&'static str, String)> {
+        let block = |text: &Option<String>| {
+            text.as_deref()
+                .unwrap_or_default()
+                .trim_end_matches('\n')
+                .to_string()
+        };
+        let about = block(&self.about)
+            .lines()
+            .map(|line| layout::wrap(line, width).join("\n"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        // styles option names and their placeholders apart, like `--port` and `<number>`.
+        let paint_option = |name: &str| match name.rfind(" <").or_else(|| name.rfind(" [")) {
+            Some(split) => format!(
+                "{} {}",
+                theme.name.paint(&name[..split]),
+                theme.placeholder.paint(&name[split + 1..])
+            ),
+            None => theme.name.paint(name),
+        };
+        let paint_argument = |name: &str| theme.placeholder.paint(name);
+        let paint_command = |name: &str| theme.name.paint(name);
+        let render = |sections: Vec<Section>, paint: &dyn Fn(&str) -> String| {
+            let rendered: String = sections
+                .into_iter()
+                .map(|(title, rows)| {
+                    format!(
+                        "{}\n{}",
+                        theme.heading.paint(&format!("{title}:")),
+                        layout::table(&rows, width, paint)
+                    )
+                })
+                .collect();
+            rendered.trim_end_matches('\n').to_string()
+        };
+        let examples = match self.examples.is_empty() {
+            true => Vec::new(),
+            false => vec![("examples".to_string(), self.examples.clone())],
+        };
+
+        vec![
+            ("name", format!("{prefix}{}", self.names[0])),
+            ("version", page.version.clone().unwrap_or_default()),
+            ("desc", block(&self.desc)),
+            ("before_help", block(&self.before_help)),
+            ("about", about),
+            ("usage", self.generate_usages(prefix).join("\n")),
+            (
+                "arguments",
+                render(self.argument_sections(), &paint_argument),
+            ),
+            ("options", render(self.option_sections(), &paint_option)),
+            (
+                "commands",
+                render(self.command_sections(page.help_command), &paint_command),
+            ),
+            ("examples", render(examples, &paint_command)),
+            ("after_help", block(&self.after_help)),
+        ]
+    }
+
+    /// generates a usage string with a prefix, spelling out required options and named
+    /// arguments. commands with subcommands that can't run on their own end with `<command>`.
     pub fn generate_usage(&self, prefix: &str) -> String {
-        let mut builder = String::from_str(prefix).unwrap();
-        builder.push_str(&self.names.get(0).unwrap());
-        if self.options.len() > 0 {
+        let mut builder = String::from(prefix);
+        builder.push_str(&self.names[0]);
+        let options = self.options.iter().filter(|option| !option.hidden);
+        for option in options.clone().filter(|option| option.required) {
+            builder.push(' ');
+            builder.push_str(&option.usage());
+        }
+        if options.clone().any(|option| !option.required) {
             builder.push_str(" [--options]");
         }
-        if self.arguments.len() > 0 {
+        if self.arguments.iter().any(|arg| arg.name.is_some()) {
+            for (i, arg) in self.arguments.iter().enumerate() {
+                builder.push(' ');
+                builder.push_str(&arg.usage(i));
+            }
+        } else if !self.arguments.is_empty() {
             builder.push_str(" [<arguments>]");
         }
-        if self.children.len() > 0 {
+        if !self.children.is_empty() && !self.runs_alone() {
             builder.push_str(" <command>");
         }
 
         builder
     }
 
-    /// generates arguments string with prefix and separator.
-    pub fn generate_args(&self, prefix: &str, separator: &str) -> String {
-        let mut builder = String::new();
-        for (i, arg) in self.arguments.iter().enumerate() {
-            builder.push_str(&format!(
-                "{}{}: {}{}{}",
-                prefix,
-                if arg.array {
-                    if i != 0 {
-                        "<everything else>".to_string()
-                    } else {
-                        "all arguments".to_string()
-                    }
-                } else {
-                    format!("#{i}")
-                },
-                arg.desc,
-                if arg.required { " (required)" } else { "" },
-                separator
-            ));
+    /// generates every usage line with a prefix. commands with subcommands that can also run on
+    /// their own get a second line for running a subcommand.
+    pub fn generate_usages(&self, prefix: &str) -> Vec<String> {
+        let mut lines = vec![self.generate_usage(prefix)];
+        if !self.children.is_empty() && self.runs_alone() {
+            lines.push(format!("{prefix}{} <command>", self.names[0]));
         }
 
-        builder
+        lines
+    }
+
+    /// checks if the command does something without a subcommand, which is when it has an action
+    /// or takes arguments.
+    fn runs_alone(&self) -> bool {
+        self.action.is_some() || !self.arguments.is_empty()
+    }
+
+    /// generates arguments string with prefix and separator.
+    pub fn generate_args(&self, prefix: &str, separator: &str) -> String {
+        Self::join_rows(self.argument_rows(), prefix, separator)
     }
 
     /// generates options string with prefix and separator.
     pub fn generate_opts(&self, prefix: &str, separator: &str) -> String {
-        let mut builder = String::new();
-        for opt in &self.options {
-            builder.push_str(&format!(
-                "{}{}: {} ({}){}",
-                prefix,
-                opt.names.join(", "),
-                opt.desc,
-                if opt.required {
-                    "required"
-                } else {
-                    "not required"
-                },
-                separator
-            ));
-        }
-
-        builder
+        Self::join_rows(self.option_rows(), prefix, separator)
     }
 
     /// generates subcommands string with prefix and separator.
     pub fn generate_sub_commands(&self, prefix: &str, separator: &str) -> String {
-        let mut builder = String::new();
-        for command in &self.children {
-            builder.push_str(&format!(
-                "{}{}: {}{}",
-                prefix,
-                command.names.join(", "),
-                command
-                    .desc
+        Self::join_rows(self.command_rows(), prefix, separator)
+    }
+
+    /// joins help rows as `name: description`, each with a prefix and separator.
+    fn join_rows(rows: Vec<(String, String)>, prefix: &str, separator: &str) -> String {
+        rows.iter()
+            .map(|(name, desc)| format!("{prefix}{name}: {desc}{separator}"))
+            .collect()
+    }
+
+    /// gets the names of the subcommands that aren't hidden.
+    fn visible_names(&self) -> impl Iterator<Item = &str> {
+        self.children
+            .iter()
+            .filter(|child| !child.hidden)
+            .flat_map(|child| child.names.iter().map(|name| name.as_str()))
+    }
+
+    /// lists the names and descriptions of the arguments shown in help.
+    fn argument_rows(&self) -> Vec<(String, String)> {
+        Self::flatten(self.argument_sections())
+    }
+
+    /// lists the names and descriptions of the options shown in help.
+    fn option_rows(&self) -> Vec<(String, String)> {
+        Self::flatten(self.option_sections())
+    }
+
+    /// lists the names and descriptions of the subcommands shown in help.
+    fn command_rows(&self) -> Vec<(String, String)> {
+        Self::flatten(self.command_sections(Self::help_command_of(&[self])))
+    }
+
+    /// groups the arguments shown in help into sections by heading.
+    fn argument_sections(&self) -> Vec<Section> {
+        let rows = self.arguments.iter().enumerate().map(|(i, arg)| {
+            let name = if let Some(name) = &arg.name {
+                name.clone()
+            } else if arg.array {
+                if i != 0 {
+                    "<everything else>".to_string()
+                } else {
+                    "all arguments".to_string()
+                }
+            } else {
+                format!("#{i}")
+            };
+            let required = if arg.required { " (required)" } else { "" };
+            let row = (name, format!("{}{required}", arg.desc));
+            (arg.heading.as_deref(), arg.order, row)
+        });
+        Self::sections("arguments", rows)
+    }
+
+    /// groups the options shown in help into sections by heading.
+    fn option_sections(&self) -> Vec<Section> {
+        let rows = self.options.iter().filter(|opt| !opt.hidden).map(|opt| {
+            let name = match &opt.value {
+                Some(ValueName {
+                    name,
+                    required: true,
+                }) => format!("{} <{name}>", opt.names.join(", ")),
+                Some(ValueName { name, .. }) => format!("{} [{name}]", opt.names.join(", ")),
+                None => opt.names.join(", "),
+            };
+            let required = if opt.required {
+                "required"
+            } else {
+                "not required"
+            };
+            let deprecated = match &opt.deprecated {
+                Some(message) => format!("; deprecated, {message}"),
+                None => String::new(),
+            };
+            let row = (name, format!("{} ({required}{deprecated})", opt.desc));
+            (opt.heading.as_deref(), opt.order, row)
+        });
+        Self::sections("options", rows)
+    }
+
+    /// groups the subcommands shown in help into sections by category. the `help` subcommand is
+    /// listed last in the `commands` section when it's on.
+    fn command_sections(&self, help_command: bool) -> Vec<Section> {
+        let rows = self
+            .children
+            .iter()
+            .filter(|command| !command.hidden)
+            .map(|command| {
+                let mut desc = command
+                    .summary
                     .clone()
-                    .unwrap_or("(no description)".to_string()),
-                separator,
-            ));
+                    .or_else(|| command.desc.clone())
+                    .unwrap_or_else(|| "(no description)".to_string());
+                if let Some(message) = &command.deprecated {
+                    desc.push_str(&format!(" (deprecated, {message})"));
+                }
+                let row = (command.names.join(", "), desc);
+                (command.category.as_deref(), command.order, row)
+            });
+        let mut sections = Self::sections("commands", rows);
+
+        if help_command && !self.children.is_empty() {
+            let row = (
+                "help [command...]".to_string(),
+                "Show help for a command".to_string(),
+            );
+            match sections.iter_mut().find(|(title, _)| title == "commands") {
+                Some((_, rows)) => rows.push(row),
+                None => sections.insert(0, ("commands".to_string(), vec![row])),
+            }
         }
 
-        builder
+        sections
+    }
+
+    /// sorts rows by their position, keeping the order of rows without one, and groups them
+    /// into sections by heading. rows without a heading go in the `title` section, which comes
+    /// first, and the other sections follow in the order their first row appears.
+    fn sections<'a>(
+        title: &str,
+        rows: impl Iterator<Item = (Option<&'a str>, Option<usize>, (String, String))>,
+    ) -> Vec<Section> {
+        let mut rows: Vec<_> = rows.collect();
+        rows.sort_by_key(|(_, order, _)| order.unwrap_or(usize::MAX));
+
+        let mut sections: Vec<Section> = vec![(title.to_string(), Vec::new())];
+        for (heading, _, row) in rows {
+            let heading = heading.unwrap_or(title);
+            match sections.iter_mut().find(|(title, _)| title == heading) {
+                Some((_, rows)) => rows.push(row),
+                None => sections.push((heading.to_string(), vec![row])),
+            }
+        }
+        sections.retain(|(_, rows)| !rows.is_empty());
+
+        sections
+    }
+
+    /// lists the rows of sections one after another.
+    fn flatten(sections: Vec<Section>) -> Vec<(String, String)> {
+        sections.into_iter().flat_map(|(_, rows)| rows).collect()
     }
 }
@@ -1,8 +1,13 @@
-use icicle::Command;
+use std::process::ExitCode;
 
-fn main() {
+use icicle::{CLIArgument, CLIOption, Command, ValueParser};
+
+fn main() -> ExitCode {
     let mut program = Command::new("human");
-    program.desc("Greet people and add numbers.");
+    program
+        .desc("Greet people and add numbers.")
+        .version(icicle::version!())
+        .target_metadata();
 
     program
         .command("greet")
@@ -19,29 +24,32 @@ fn main() {
     program
         .command("add")
         .desc("Add two numbers.")
-        .option("-x, --x", "First number")
-        .option("-y, --y", "Second number")
+        .add_option(CLIOption::new("-x, --x <n>", "First number").parser(ValueParser::int()))
+        .add_option(CLIOption::new("-y, --y <n>", "Second number").parser(ValueParser::int()))
         .action(|args| {
-            let x = args.get_or::<i32>("-x", "--x").unwrap();
-            let y = args.get_or::<i32>("-y", "--y").unwrap();
+            let x = args.value::<i64>("-x").unwrap();
+            let y = args.value::<i64>("-y").unwrap();
             println!("{x} + {y} = {}", x + y);
 
             Ok(())
         })
         .command("infinite")
         .desc("Add any amount of numbers.")
-        .array_argument("Numbers you want to add.")
+        .add_argument(
+            CLIArgument::new("Numbers you want to add.")
+                .required(false)
+                .array(true)
+                .parser(ValueParser::int()),
+        )
         .action(|args| {
             let mut sum = 0;
-            for arg in args.iter() {
-                sum += arg.parse::<i32>().unwrap();
+            for i in 0..args.pos.len() {
+                sum += args.value_at::<i64>(i).unwrap();
             }
             println!("{} = {}", args.pos.join(" + "), sum);
 
             Ok(())
         });
 
-    if let Err(_) = program.run_env() {
-        std::process::exit(1);
-    }
+    program.main()
 }
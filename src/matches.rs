@@ -0,0 +1,36 @@
+use crate::args::Args;
+
+#[derive(Clone)]
+/// the result of parsing a command line without running it.
+pub struct Matches {
+    /// names of the matched commands, starting with the root.
+    pub path: Vec<String>,
+    /// options and arguments given to each matched command, starting with the root.
+    pub levels: Vec<Args>,
+    /// every option and argument, as the matched command's action would get them.
+    pub args: Args,
+    /// whether the user asked for help, in which case the arguments weren't checked.
+    pub help_asked: bool,
+    /// whether the user asked for the version, in which case the arguments weren't checked.
+    pub version_asked: bool,
+    /// warnings about deprecated commands and options that were used, which aren't printed.
+    pub warnings: Vec<String>,
+}
+
+impl Matches {
+    /// gets the name of the matched command.
+    pub fn command(&self) -> &str {
+        &self.path[self.path.len() - 1]
+    }
+
+    /// gets the name of the subcommand given to the root command, if any.
+    pub fn subcommand(&self) -> Option<&str> {
+        self.path.get(1).map(|name| name.as_str())
+    }
+
+    /// gets the options and arguments given to a matched command by name.
+    pub fn level(&self, name: &str) -> Option<&Args> {
+        let index = self.path.iter().position(|n| n == name)?;
+        self.levels.get(index)
+    }
+}
@@ -0,0 +1,155 @@
+use core::fmt;
+use std::{
+    io::{self, IsTerminal, Write},
+    sync::{Arc, Mutex},
+};
+
+use crate::style::Style;
+
+/// a writer shared between commands.
+type Writer = Arc<Mutex<dyn Write + Send>>;
+/// function that formats an error message before it's written.
+type FormatError = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+#[derive(Clone)]
+/// where built-in output like help screens, errors and warnings is written to.
+pub struct Output {
+    /// writer for regular output, like help asked for by the user.
+    out: Writer,
+    /// writer for errors and warnings.
+    err: Writer,
+    /// function that formats error messages.
+    format_error: Option<FormatError>,
+    /// whether regular output goes to a terminal.
+    out_terminal: bool,
+    /// whether errors and warnings go to a terminal.
+    err_terminal: bool,
+}
+
+impl Output {
+    /// creates an output writing to stdout and stderr.
+    pub fn new() -> Output {
+        Output {
+            out: Arc::new(Mutex::new(io::stdout())),
+            err: Arc::new(Mutex::new(io::stderr())),
+            format_error: None,
+            out_terminal: io::stdout().is_terminal(),
+            err_terminal: io::stderr().is_terminal(),
+        }
+    }
+
+    /// sets the writer for regular output.
+    pub fn stdout<W: Write + Send + 'static>(mut self, writer: W) -> Self {
+        self.out = Arc::new(Mutex::new(writer));
+        self.out_terminal = false;
+        self
+    }
+
+    /// sets the writer for errors and warnings.
+    pub fn stderr<W: Write + Send + 'static>(mut self, writer: W) -> Self {
+        self.err = Arc::new(Mutex::new(writer));
+        self.err_terminal = false;
+        self
+    }
+
+    /// sets a function that formats every error message before it's written, like to color it
+    /// or add a prefix.
+    pub fn format_error<T: Fn(&str) -> String + Send + Sync + 'static>(
+        mut self,
+        format: T,
+    ) -> Self {
+        self.format_error = Some(Arc::new(format));
+        self
+    }
+
+    /// writes text to the regular output.
+    pub fn write_out(&self, text: &str) {
+        Self::write(&self.out, text);
+    }
+
+    /// writes text to the error output.
+    pub fn write_err(&self, text: &str) {
+        Self::write(&self.err, text);
+    }
+
+    /// formats an error message and writes it to the error output on its own line.
+    pub fn error(&self, message: &str) {
+        self.styled_error(message, &Style::new());
+    }
+
+    /// formats an error message, styles it and writes it to the error output on its own line.
+    pub(crate) fn styled_error(&self, message: &str, style: &Style) {
+        let message = match &self.format_error {
+            Some(format) => format(message),
+            None => message.to_string(),
+        };
+        self.write_err(&format!("{}\n", style.paint(&message)));
+    }
+
+    /// writes a warning to the error output on its own line.
+    pub fn warning(&self, message: &str) {
+        self.write_err(&format!("{message}\n"));
+    }
+
+    /// checks if regular output goes to a terminal. custom writers never count as one.
+    pub(crate) fn out_terminal(&self) -> bool {
+        self.out_terminal
+    }
+
+    /// checks if errors and warnings go to a terminal. custom writers never count as one.
+    pub(crate) fn err_terminal(&self) -> bool {
+        self.err_terminal
+    }
+
+    /// writes text to a writer. errors are ignored, as there is nowhere left to report them.
+    fn write(writer: &Writer, text: &str) {
+        let mut writer = writer.lock().unwrap_or_else(|error| error.into_inner());
+        let _ = writer.write_all(text.as_bytes());
+        let _ = writer.flush();
+    }
+}
+
+impl Default for Output {
+    /// creates an output writing to stdout and stderr.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Output {
+    /// formats an output, which has nothing to show.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Output").finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// an in-memory writer that can be read after writing, useful to capture output in tests.
+pub struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Buffer {
+    /// creates an empty buffer.
+    pub fn new() -> Buffer {
+        Self::default()
+    }
+
+    /// gets everything written so far.
+    pub fn contents(&self) -> String {
+        let bytes = self.0.lock().unwrap_or_else(|error| error.into_inner());
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+impl Write for Buffer {
+    /// appends bytes to the buffer.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut bytes = self.0.lock().unwrap_or_else(|error| error.into_inner());
+        bytes.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    /// does nothing, as the buffer is always up to date.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
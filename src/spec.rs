@@ -0,0 +1,280 @@
+use core::fmt;
+use std::error::Error;
+
+#[derive(Debug, Clone, PartialEq)]
+/// an error in an option, argument or command spec string.
+pub struct SpecError {
+    /// spec that couldn't be parsed.
+    pub spec: String,
+    /// what is wrong with the spec.
+    pub message: String,
+}
+
+impl fmt::Display for SpecError {
+    /// formats a spec error.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid spec {:?}: {}", self.spec, self.message)
+    }
+}
+
+impl Error for SpecError {}
+
+#[derive(Debug, Clone, PartialEq)]
+/// the placeholder for the value an option takes, like `<number>` or `[when]`.
+pub struct ValueName {
+    /// name shown in help.
+    pub name: String,
+    /// whether the value must be given when the option is used.
+    pub required: bool,
+}
+
+/// a parsed positional argument spec.
+pub(crate) struct ArgumentSpec {
+    /// name of the argument.
+    pub name: String,
+    /// whether the argument is required.
+    pub required: bool,
+    /// whether the argument captures multiple values.
+    pub array: bool,
+}
+
+/// creates a spec error.
+fn error(spec: &str, message: &str) -> SpecError {
+    SpecError {
+        spec: spec.to_string(),
+        message: message.to_string(),
+    }
+}
+
+/// splits a placeholder like `<name>`, `[name]` or `<name...>` into its name, whether it is
+/// required and whether it captures multiple values.
+fn parse_placeholder(spec: &str, token: &str) -> Result<(String, bool, bool), SpecError> {
+    let (inner, required) = if let Some(rest) = token.strip_prefix('<') {
+        let inner = rest
+            .strip_suffix('>')
+            .ok_or_else(|| error(spec, &format!("{token:?} is missing a closing `>`")))?;
+        (inner, true)
+    } else if let Some(rest) = token.strip_prefix('[') {
+        let inner = rest
+            .strip_suffix(']')
+            .ok_or_else(|| error(spec, &format!("{token:?} is missing a closing `]`")))?;
+        (inner, false)
+    } else {
+        (token, true)
+    };
+
+    let (name, array) = match inner.strip_suffix("...") {
+        Some(name) => (name, true),
+        None => (inner, false),
+    };
+    if name.is_empty() {
+        return Err(error(spec, "placeholder names can't be empty"));
+    }
+    if let Some(ch) = name
+        .chars()
+        .find(|ch| ch.is_whitespace() || "<>[]".contains(*ch))
+    {
+        return Err(error(
+            spec,
+            &format!("{ch:?} isn't allowed in placeholder names"),
+        ));
+    }
+
+    Ok((name.to_string(), required, array))
+}
+
+/// parses an option spec like `-p, --port <number>` or `--color [when]` into its names and the
+/// value it takes, if any. names can be separated by commas, spaces or `|`.
+pub(crate) fn parse_option(spec: &str) -> Result<(Vec<String>, Option<ValueName>), SpecError> {
+    let mut names = Vec::new();
+    let mut value = None;
+
+    for token in spec
+        .split([',', '|', ' ', '\t'])
+        .filter(|token| !token.is_empty())
+    {
+        // allows the placeholder to be attached with `=`, like `--port=<number>`.
+        let (token, attached) = match token.split_once('=') {
+            Some((name, placeholder)) => (name, Some(placeholder)),
+            None => (token, None),
+        };
+
+        for token in [Some(token), attached].into_iter().flatten() {
+            if token.starts_with('<') || token.starts_with('[') {
+                if names.is_empty() {
+                    return Err(error(
+                        spec,
+                        "the value placeholder must come after the names",
+                    ));
+                }
+                if value.is_some() {
+                    return Err(error(spec, "options can only take one value"));
+                }
+                let (name, required, array) = parse_placeholder(spec, token)?;
+                if array {
+                    return Err(error(spec, "options can't take multiple values"));
+                }
+                value = Some(ValueName { name, required });
+            } else if value.is_some() {
+                return Err(error(
+                    spec,
+                    &format!("unexpected {token:?} after the value placeholder"),
+                ));
+            } else if (token.starts_with("--") && token.len() > 2)
+                || (!token.starts_with("--")
+                    && token.starts_with('-')
+                    && token.chars().count() == 2)
+            {
+                names.push(token.to_string());
+            } else if token.starts_with('-') && !token.starts_with("--") {
+                return Err(error(
+                    spec,
+                    &format!(
+                        "{token:?} should be a single character after `-`, or start with `--`"
+                    ),
+                ));
+            } else {
+                return Err(error(
+                    spec,
+                    &format!("{token:?} isn't an option name, which start with `-` or `--`"),
+                ));
+            }
+        }
+    }
+
+    if names.is_empty() {
+        return Err(error(spec, "options need at least one name"));
+    }
+
+    Ok((names, value))
+}
+
+/// parses a positional argument spec like `<name>`, `[name]`, `<name...>` or `<name>...`. a bare
+/// name is a required argument.
+pub(crate) fn parse_argument(spec: &str) -> Result<ArgumentSpec, SpecError> {
+    let token = spec.trim();
+    if token.is_empty() {
+        return Err(error(spec, "arguments need a name"));
+    }
+    if token.starts_with('-') {
+        return Err(error(spec, "argument names can't start with `-`"));
+    }
+
+    let (token, trailing_array) = match token.strip_suffix("...") {
+        Some(token) if token.ends_with('>') || token.ends_with(']') => (token, true),
+        _ => (token, false),
+    };
+    let (name, required, array) = parse_placeholder(spec, token)?;
+
+    Ok(ArgumentSpec {
+        name,
+        required,
+        array: array || trailing_array,
+    })
+}
+
+/// parses a command spec like `clone <source> [dest]` into its name and argument specs.
+pub(crate) fn parse_command(spec: &str) -> Result<(String, Vec<ArgumentSpec>), SpecError> {
+    let mut tokens = spec.split_whitespace();
+    let name = tokens
+        .next()
+        .ok_or_else(|| error(spec, "commands need a name"))?;
+    if name.starts_with(['-', '<', '[']) {
+        return Err(error(spec, "the command name must come first"));
+    }
+
+    let arguments = tokens
+        .map(|token| {
+            if token.starts_with(['<', '[']) {
+                parse_argument(token).map_err(|error| SpecError {
+                    spec: spec.to_string(),
+                    ..error
+                })
+            } else {
+                Err(error(
+                    spec,
+                    &format!("{token:?} should be an argument like `<name>` or `[name]`"),
+                ))
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((name.to_string(), arguments))
+}
+
+/// placeholders a help template can use, one for every part of a help screen.
+pub(crate) const TEMPLATE_PLACEHOLDERS: [&str; 11] = [
+    "name",
+    "version",
+    "desc",
+    "before_help",
+    "about",
+    "usage",
+    "arguments",
+    "options",
+    "commands",
+    "examples",
+    "after_help",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+/// a piece of a help template.
+pub(crate) enum TemplatePart {
+    /// text shown as is.
+    Text(String),
+    /// a placeholder replaced with a part of the help screen, like `{options}`.
+    Placeholder(String),
+}
+
+/// parses a help template like `{usage}\n\n{options}` into text and placeholders. `{{` and `}}`
+/// stand for literal braces.
+pub(crate) fn parse_template(spec: &str) -> Result<Vec<TemplatePart>, SpecError> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut chars = spec.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(ch) => name.push(ch),
+                        None => {
+                            let message = format!("`{{{name}` is missing a closing `}}`");
+                            return Err(error(spec, &message));
+                        }
+                    }
+                }
+                if !TEMPLATE_PLACEHOLDERS.contains(&name.as_str()) {
+                    return Err(error(
+                        spec,
+                        &format!(
+                            "unknown placeholder `{{{name}}}`, expected one of {}",
+                            TEMPLATE_PLACEHOLDERS.join(", ")
+                        ),
+                    ));
+                }
+                if !text.is_empty() {
+                    parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+                }
+                parts.push(TemplatePart::Placeholder(name));
+            }
+            '}' => return Err(error(spec, "unmatched `}`, use `}}` for a literal brace")),
+            _ => text.push(ch),
+        }
+    }
+    if !text.is_empty() {
+        parts.push(TemplatePart::Text(text));
+    }
+
+    Ok(parts)
+}
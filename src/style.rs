@@ -0,0 +1,158 @@
+use std::{env, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// a terminal color.
+pub enum Color {
+    /// black.
+    Black,
+    /// red.
+    Red,
+    /// green.
+    Green,
+    /// yellow.
+    Yellow,
+    /// blue.
+    Blue,
+    /// magenta.
+    Magenta,
+    /// cyan.
+    Cyan,
+    /// white.
+    White,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// an ANSI text style, like bold red. the default style leaves text unchanged.
+pub struct Style {
+    /// SGR codes of the style, like `1;31`.
+    codes: Vec<u8>,
+}
+
+impl Style {
+    /// creates a style that leaves text unchanged.
+    pub fn new() -> Style {
+        Self::default()
+    }
+
+    /// makes text bold.
+    pub fn bold(self) -> Self {
+        self.code(1)
+    }
+
+    /// makes text dim.
+    pub fn dim(self) -> Self {
+        self.code(2)
+    }
+
+    /// makes text italic.
+    pub fn italic(self) -> Self {
+        self.code(3)
+    }
+
+    /// underlines text.
+    pub fn underline(self) -> Self {
+        self.code(4)
+    }
+
+    /// colors text.
+    pub fn fg(self, color: Color) -> Self {
+        self.code(30 + color as u8)
+    }
+
+    /// adds an SGR code.
+    fn code(mut self, code: u8) -> Self {
+        self.codes.push(code);
+        self
+    }
+
+    /// wraps text in the escape codes of the style.
+    pub fn paint(&self, text: &str) -> String {
+        if self.codes.is_empty() || text.is_empty() {
+            return text.to_string();
+        }
+
+        let codes: Vec<String> = self.codes.iter().map(u8::to_string).collect();
+        format!("\x1b[{}m{text}\x1b[0m", codes.join(";"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// styles used for help, errors and warnings when color is on.
+pub struct Theme {
+    /// section headings, like `options:`.
+    pub heading: Style,
+    /// option, argument and command names.
+    pub name: Style,
+    /// value placeholders, like `<n>`.
+    pub placeholder: Style,
+    /// error messages.
+    pub error: Style,
+    /// warning messages.
+    pub warning: Style,
+}
+
+impl Theme {
+    /// creates a theme that leaves text unchanged.
+    pub fn plain() -> Theme {
+        Theme {
+            heading: Style::new(),
+            name: Style::new(),
+            placeholder: Style::new(),
+            error: Style::new(),
+            warning: Style::new(),
+        }
+    }
+}
+
+impl Default for Theme {
+    /// creates the default theme: bold underlined headings, bold cyan names, green placeholders,
+    /// bold red errors and bold yellow warnings.
+    fn default() -> Self {
+        Theme {
+            heading: Style::new().bold().underline(),
+            name: Style::new().bold().fg(Color::Cyan),
+            placeholder: Style::new().fg(Color::Green),
+            error: Style::new().bold().fg(Color::Red),
+            warning: Style::new().bold().fg(Color::Yellow),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+/// when to color output.
+pub enum ColorChoice {
+    /// colors output written to a terminal, unless `NO_COLOR` is set or `CLICOLOR_FORCE` is.
+    #[default]
+    Auto,
+    /// always colors output.
+    Always,
+    /// never colors output.
+    Never,
+}
+
+impl ColorChoice {
+    /// checks if output to a stream should be colored, given whether the stream is a terminal.
+    pub(crate) fn enabled(self, terminal: bool) -> bool {
+        let var = |name| env::var_os(name).filter(|value| !value.is_empty());
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto if var("NO_COLOR").is_some() => false,
+            Self::Auto => var("CLICOLOR_FORCE").map_or(terminal, |force| force != "0"),
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    /// parses `auto`, `always` or `never`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err("expected auto, always or never".to_string()),
+        }
+    }
+}
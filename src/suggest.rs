@@ -0,0 +1,52 @@
+/// finds the candidate closest to a mistyped value, if any is close enough to be a likely typo.
+/// option names are compared without their dashes, and single characters, like `-x`, are too
+/// short to tell a typo from a different name.
+pub(crate) fn suggest<'a, I: IntoIterator<Item = &'a str>>(
+    value: &str,
+    candidates: I,
+) -> Option<String> {
+    let stem = |name: &'a str| name.trim_start_matches('-');
+    let value = value.trim_start_matches('-');
+    let length = value.chars().count();
+    if length <= 1 {
+        return None;
+    }
+
+    // allows roughly one edit for every three characters.
+    let limit = (length / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (distance(value, stem(candidate)), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+/// counts the edits needed to turn one string into another, where an edit is inserting,
+/// removing or replacing a character, or swapping two neighbouring ones.
+fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut table = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in table.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in table[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut best = (table[i - 1][j] + 1)
+                .min(table[i][j - 1] + 1)
+                .min(table[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(table[i - 2][j - 2] + 1);
+            }
+            table[i][j] = best;
+        }
+    }
+
+    table[a.len()][b.len()]
+}
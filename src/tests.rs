@@ -1,23 +1,40 @@
-use crate::{Command, args::Args};
+use std::{
+    env,
+    error::Error,
+    fmt, fs,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    ArgError, Buffer, CLIArgument, CLIOption, Color, ColorChoice, Command, CommandError, Config,
+    ExitError, ExitStatus, Output, Style, Theme, ValueName, ValueParser, ValueSource, args::Args,
+    exit,
+};
 
 fn dummy_command() -> Command {
-    Command {
-        names: vec!["root".to_string()],
-        children: vec![Command {
-            names: vec!["sub".to_string(), "alias".to_string()],
-            children: vec![],
-            arguments: vec![],
-            options: vec![],
-            help: None,
-            action: None,
-            desc: None,
-        }],
-        arguments: vec![],
-        options: vec![],
-        help: None,
-        action: None,
-        desc: None,
-    }
+    let mut root = Command::new("root");
+    root.command("sub").alias("alias");
+    root
+}
+
+/// sets an action on a command that stores the `Args` it receives.
+fn capture(command: &mut Command) -> Arc<Mutex<Option<Args>>> {
+    let captured = Arc::new(Mutex::new(None));
+    let sink = captured.clone();
+    command.action(move |args| {
+        *sink.lock().unwrap() = Some(args);
+        Ok(())
+    });
+    captured
+}
+
+/// runs a command and returns the `Args` its action received.
+fn run_capture(command: &mut Command, args: Vec<&str>) -> Args {
+    let captured = capture(command);
+    command.run_str(args).unwrap();
+    let args = captured.lock().unwrap().take();
+    args.expect("action wasn't run")
 }
 
 #[test]
@@ -36,8 +53,8 @@ fn test_parse_short_option() {
     let args = vec!["-v=true".to_string(), "-f=false".to_string()];
     let (_, parsed, _) = Args::parse(&cmd, args);
 
-    assert_eq!(parsed.get_string("v").unwrap(), "true");
-    assert_eq!(parsed.get_string("f").unwrap(), "false");
+    assert_eq!(parsed.get_string("-v").unwrap(), "true");
+    assert_eq!(parsed.get_string("-f").unwrap(), "false");
 }
 
 #[test]
@@ -70,11 +87,11 @@ fn test_has_and_get() {
     let (_, parsed, _) = Args::parse(&cmd, args);
 
     assert!(parsed.has("--enable"));
-    assert!(parsed.has("d"));
+    assert!(parsed.has("-d"));
     assert!(!parsed.has("nonexistent"));
 
-    assert_eq!(parsed.get::<bool>("--enable").unwrap(), true);
-    assert_eq!(parsed.get::<bool>("d").unwrap(), false);
+    assert!(parsed.get::<bool>("--enable").unwrap());
+    assert!(!parsed.get::<bool>("-d").unwrap());
 }
 
 #[test]
@@ -143,7 +160,7 @@ fn test_add_alias() {
 fn test_add_option_and_argument() {
     let mut cmd = Command::new("test");
     cmd.option("-o, --option", "an option")
-        .argument("an argument");
+        .argument("<arg>", "an argument");
     assert_eq!(cmd.options.len(), 1);
     assert_eq!(cmd.arguments.len(), 1);
     assert_eq!(
@@ -178,17 +195,33 @@ fn test_add_subcommand() {
 fn test_generate_usage() {
     let mut cmd = Command::new("app");
     cmd.option("-v, --verbose", "verbose mode")
-        .argument("filename");
+        .add_argument(CLIArgument::new("filename"));
     let usage = cmd.generate_usage(" ");
-    assert!(usage.contains("[--options]"));
-    assert!(usage.contains("[<arguments>]"));
+    assert_eq!(usage, " app -v [<arguments>]");
+
+    let mut human = Command::new("human");
+    let add = human
+        .command("add")
+        .option("-x <n>", "First number")
+        .opt_option("--verbose", "Print more")
+        .action(|_| Ok(()));
+    add.command("infinite [numbers...]");
+    assert_eq!(
+        add.generate_usages("human "),
+        vec!["human add -x <n> [--options]", "human add <command>"]
+    );
+    assert_eq!(
+        add.children[0].generate_usage("human add "),
+        "human add infinite [numbers...]"
+    );
+    assert_eq!(human.generate_usages(""), vec!["human <command>"]);
 }
 
 #[test]
 fn test_generate_help_sections() {
     let mut cmd = Command::new("app");
     cmd.option("-v, --verbose", "verbose mode")
-        .argument("filename")
+        .argument("<filename>", "file to read")
         .command("sub");
     let help = cmd.generate_help();
     assert!(help.contains("usage:"));
@@ -209,8 +242,8 @@ fn test_args_parse_long_option() {
 fn test_args_parse_short_option() {
     let cmd = Command::new("app");
     let (_, args, _) = Args::parse(&cmd, vec!["-f=true".into()]);
-    assert!(args.has("f"));
-    assert_eq!(args.get_string("f").unwrap(), "true");
+    assert!(args.has("-f"));
+    assert_eq!(args.get_string("-f").unwrap(), "true");
 }
 
 #[test]
@@ -239,3 +272,1323 @@ fn test_args_range() {
     let range: Vec<i32> = args.range(0..3).unwrap();
     assert_eq!(range, vec![1, 2, 3]);
 }
+
+#[test]
+fn test_config_parse_sections() {
+    let text = "# root values\nverbose = true\n\n[add]\nx = 5 ; inline comment\ny = \"a \\\"quoted\\\" value\"\n\n[add.infinite]\nname = 'literal # kept'\n";
+    let config = Config::parse(Path::new("test.toml"), text).unwrap();
+
+    assert_eq!(config.get("", "verbose").unwrap().value, "true");
+    assert_eq!(config.get("add", "x").unwrap().value, "5");
+    assert_eq!(config.get("add", "x").unwrap().line, 5);
+    assert_eq!(config.get("add", "y").unwrap().value, "a \"quoted\" value");
+    assert_eq!(
+        config.get("add.infinite", "name").unwrap().value,
+        "literal # kept"
+    );
+    assert!(config.get("", "x").is_none());
+}
+
+#[test]
+fn test_config_parse_errors() {
+    let error = Config::parse(Path::new("bad.ini"), "[add\nx = 1").unwrap_err();
+    assert_eq!(error.line, Some(1));
+
+    let error = Config::parse(Path::new("bad.ini"), "x = 1\njust text").unwrap_err();
+    assert_eq!(error.line, Some(2));
+    assert_eq!(error.to_string(), "bad.ini:2: expected `key = value`");
+}
+
+#[test]
+fn test_option_default_and_env() {
+    let mut cmd = Command::new("app");
+    cmd.env_source(|var| (var == "ICICLE_TEST_DEFAULT_AND_ENV").then(|| "from env".to_string()))
+        .add_option(CLIOption::new("-n, --name", "a name").default("fallback"))
+        .add_option(
+            CLIOption::new("--env", "from the environment").env("ICICLE_TEST_DEFAULT_AND_ENV"),
+        );
+
+    let args = run_capture(&mut cmd, vec![]);
+    assert_eq!(args.get_string("-n").unwrap(), "fallback");
+    assert_eq!(args.get_string("--name").unwrap(), "fallback");
+    assert_eq!(args.get_string("--env").unwrap(), "from env");
+
+    let args = run_capture(&mut cmd, vec!["--name=given", "--env=cli"]);
+    assert_eq!(args.get_string("--name").unwrap(), "given");
+    assert_eq!(args.get_string("--env").unwrap(), "cli");
+}
+
+#[test]
+fn test_config_precedence() {
+    let path = env::temp_dir().join(format!("icicle-precedence-{}.toml", std::process::id()));
+    fs::write(&path, "x = 1\n[add]\nx = 2\ny = 3\n").unwrap();
+
+    let mut cmd = Command::new("app");
+    cmd.config_option("-c, --config");
+    let add = cmd
+        .command("add")
+        .add_option(CLIOption::new("-x, --x", "first").default("0"))
+        .add_option(CLIOption::new("-y, --y", "second").default("0"))
+        .add_option(CLIOption::new("-z, --z", "third").default("0"));
+    let captured = capture(add);
+
+    let config = format!("--config={}", path.display());
+    cmd.run_str(vec!["add", &config, "-y=9"]).unwrap();
+    let args = captured.lock().unwrap().take().unwrap();
+    fs::remove_file(&path).unwrap();
+
+    // the [add] section is used over the root one, and the command line wins over the config.
+    assert_eq!(args.get_string("--x").unwrap(), "2");
+    assert_eq!(args.get_string("-y").unwrap(), "9");
+    assert_eq!(args.get_string("--z").unwrap(), "0");
+}
+
+#[test]
+fn test_subcommand_config() {
+    let path = env::temp_dir().join(format!("icicle-subcommand-{}.toml", std::process::id()));
+    fs::write(&path, "[sub]\nx = 2\n").unwrap();
+
+    let mut cmd = Command::new("app");
+    let sub = cmd
+        .command("sub")
+        .config_option("-c, --config")
+        .add_option(CLIOption::new("-x, --x <n>", "first").required(false));
+    let captured = capture(sub);
+
+    cmd.run_str(vec!["sub", "-c", path.to_str().unwrap()])
+        .unwrap();
+    let args = captured.lock().unwrap().take().unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(args.get_string("--x").unwrap(), "2");
+
+    let error = cmd
+        .run_str(vec!["sub", "-c", "/nonexistent/icicle.toml"])
+        .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<CommandError>(),
+        Some(CommandError::Config(_))
+    ));
+}
+
+#[test]
+fn test_config_missing_file() {
+    let mut cmd = Command::new("app");
+    cmd.config_option("--config").action(|_| Ok(()));
+
+    let error = cmd
+        .run_str(vec!["--config=/nonexistent/icicle.toml"])
+        .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<CommandError>(),
+        Some(CommandError::Config(_))
+    ));
+}
+
+#[test]
+fn test_false_flags() {
+    let path = env::temp_dir().join(format!("icicle-false-{}.toml", std::process::id()));
+    fs::write(&path, "verbose = false\nquiet = FALSE\ncolor = true\n").unwrap();
+
+    let mut cmd = Command::new("app");
+    cmd.env_source(|var| (var == "ICICLE_TEST_FALSE_FLAG").then(|| "false".to_string()))
+        .config_option("--config")
+        .opt_option("-v, --verbose", "Print more")
+        .opt_option("-q, --quiet", "Print less")
+        .opt_option("--color", "Color output")
+        .add_option(
+            CLIOption::new("--debug", "Debug")
+                .required(false)
+                .env("ICICLE_TEST_FALSE_FLAG"),
+        )
+        .add_option(CLIOption::new("--level <n>", "Level").default("0"));
+
+    let config = format!("--config={}", path.display());
+    let args = run_capture(&mut cmd, vec![&config]);
+    fs::remove_file(&path).unwrap();
+
+    assert!(!args.has("--verbose"));
+    assert!(!args.has("--quiet"));
+    assert!(!args.has("--debug"));
+    assert!(args.has("--color"));
+    // options that take a value keep it.
+    assert_eq!(args.get::<i32>("--level"), Some(0));
+}
+
+#[test]
+fn test_value_sources() {
+    let path = env::temp_dir().join(format!("icicle-sources-{}.toml", std::process::id()));
+    fs::write(&path, "# comment\nfile = yes\n").unwrap();
+
+    let mut cmd = Command::new("app");
+    cmd.env_source(|var| (var == "ICICLE_TEST_VALUE_SOURCES").then(|| "3".to_string()))
+        .config_option("--config")
+        .opt_option("--cli", "from the command line")
+        .add_option(
+            CLIOption::new("--env", "from the environment").env("ICICLE_TEST_VALUE_SOURCES"),
+        )
+        .add_option(CLIOption::new("--file", "from the config file"))
+        .add_option(CLIOption::new("-d, --default", "from the default").default("x"));
+
+    let config = format!("--config={}", path.display());
+    let args = run_capture(&mut cmd, vec!["--cli=1", &config]);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(args.source("--cli"), Some(&ValueSource::CommandLine(0)));
+    assert_eq!(
+        args.source("--env"),
+        Some(&ValueSource::Env("ICICLE_TEST_VALUE_SOURCES".to_string()))
+    );
+    assert_eq!(args.source("--file"), Some(&ValueSource::Config(path, 2)));
+    assert_eq!(args.source("-d"), Some(&ValueSource::Default));
+    assert_eq!(args.source("--missing"), None);
+
+    let dump = args.debug_dump();
+    assert!(dump.contains("--cli=1 (command line, argument 0)\n"));
+    assert!(dump.contains("-d=x (default)\n"));
+}
+
+#[test]
+fn test_option_validator() {
+    let mut cmd = Command::new("serve");
+    cmd.add_option(CLIOption::new("-p, --port", "port to listen on").validator(
+        |v| v.parse::<u16>().is_ok_and(|p| p > 1024),
+        "must be a port above 1024",
+    ))
+    .help(|_, _, _| {})
+    .action(|args| {
+        assert_eq!(args.get::<u16>("--port"), Some(8080));
+        Ok(())
+    });
+
+    cmd.run_str(vec!["--port=8080"]).unwrap();
+
+    let error = cmd.run_str(vec!["-p=80"]).unwrap_err();
+    match error.downcast_ref::<CommandError>() {
+        Some(CommandError::InvalidValue {
+            name,
+            value,
+            reason,
+        }) => {
+            assert_eq!(name, "-p");
+            assert_eq!(value, "80");
+            assert_eq!(reason, "must be a port above 1024");
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn test_parent_option_validator() {
+    let mut cmd = Command::new("app");
+    cmd.add_option(
+        CLIOption::new("--num <n>", "number")
+            .required(false)
+            .validator(|v| v != "13", "unlucky"),
+    )
+    .help(|_, _, _| {});
+    cmd.command("sub").action(|_| Ok(()));
+
+    cmd.run_str(vec!["sub", "--num", "12"]).unwrap();
+    let error = cmd.run_str(vec!["sub", "--num", "13"]).unwrap_err();
+    assert_eq!(error.to_string(), "Invalid value \"13\" for --num: unlucky");
+}
+
+#[test]
+fn test_argument_validator() {
+    let mut cmd = Command::new("sum");
+    cmd.add_argument(
+        CLIArgument::new("numbers")
+            .array(true)
+            .validator(|v| v.parse::<i32>().is_ok(), "must be a number"),
+    )
+    .help(|_, _, _| {})
+    .action(|_| Ok(()));
+
+    cmd.run_str(vec!["1", "2"]).unwrap();
+
+    let error = cmd.run_str(vec!["1", "two"]).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid value \"two\" for #0: must be a number"
+    );
+}
+
+#[test]
+fn test_typed_values() {
+    let mut cmd = Command::new("add");
+    cmd.add_option(CLIOption::new("-x, --x", "first number").parser(ValueParser::int()))
+        .add_option(
+            CLIOption::new("-r, --ratio", "a ratio")
+                .parser(ValueParser::float())
+                .default("0.5"),
+        )
+        .add_argument(CLIArgument::new("port").parser(ValueParser::of::<u16>()))
+        .help(|_, _, _| {});
+
+    let args = run_capture(&mut cmd, vec!["-x=5", "8080"]);
+    assert_eq!(args.value::<i64>("-x"), Some(&5));
+    assert_eq!(args.value::<i64>("--x"), Some(&5));
+    assert_eq!(args.value::<f64>("--ratio"), Some(&0.5));
+    assert_eq!(args.value_at::<u16>(0), Some(&8080));
+    // asking for the wrong type doesn't re-parse.
+    assert_eq!(args.value::<i32>("-x"), None);
+
+    let error = cmd.run_str(vec!["--x=abc", "8080"]).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid value \"abc\" for --x: expected int: invalid digit found in string"
+    );
+
+    let error = cmd.run_str(vec!["-x=1", "99999"]).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<CommandError>(),
+        Some(CommandError::InvalidValue { name, .. }) if name == "#0"
+    ));
+}
+
+#[test]
+fn test_parent_option_parser() {
+    let mut cmd = Command::new("app");
+    cmd.add_option(
+        CLIOption::new("--num <n>", "number")
+            .required(false)
+            .parser(ValueParser::int()),
+    )
+    .help(|_, _, _| {});
+    let sub = capture(cmd.command("sub"));
+
+    let error = cmd.run_str(vec!["--num", "abc", "sub"]).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<CommandError>(),
+        Some(CommandError::InvalidValue { name, .. }) if name == "--num"
+    ));
+
+    cmd.run_str(vec!["--num", "5", "sub"]).unwrap();
+    let args = sub.lock().unwrap().take().unwrap();
+    assert_eq!(args.value::<i64>("--num"), Some(&5));
+    let matches = cmd.try_parse_str(vec!["--num", "5", "sub"]).ok().unwrap();
+    assert_eq!(
+        matches.level("app").unwrap().value::<i64>("--num"),
+        Some(&5)
+    );
+}
+
+#[test]
+fn test_try_getters() {
+    let args = Args::new_str(vec!["--count=42", "--name=abc", "1", "x"]);
+
+    assert_eq!(args.try_get::<i32>("--count").unwrap(), 42);
+    assert!(matches!(
+        args.try_get::<i32>("--missing"),
+        Err(ArgError::Missing(name)) if name == "--missing"
+    ));
+
+    let error = args.try_get::<i32>("--name").unwrap_err();
+    assert!(
+        matches!(&error, ArgError::Invalid { name, value, .. } if name == "--name" && value == "abc")
+    );
+    assert!(std::error::Error::source(&error).is_some());
+    assert_eq!(
+        error.to_string(),
+        "Invalid value \"abc\" for --name: invalid digit found in string"
+    );
+
+    assert_eq!(args.try_get_or::<i32>("-c", "--count").unwrap(), 42);
+    assert_eq!(
+        args.try_get_or::<i32>("-m", "--missing")
+            .unwrap_err()
+            .to_string(),
+        "Missing value for -m or --missing"
+    );
+
+    assert_eq!(args.try_at::<i32>(0).unwrap(), 1);
+    assert!(matches!(args.try_at::<i32>(1), Err(ArgError::Invalid { name, .. }) if name == "#1"));
+    assert!(matches!(args.try_at::<i32>(2), Err(ArgError::Missing(name)) if name == "#2"));
+
+    assert_eq!(args.try_range::<i32>(0..1).unwrap(), vec![1]);
+    assert!(
+        matches!(args.try_range::<i32>(0..2), Err(ArgError::Invalid { name, .. }) if name == "#1")
+    );
+    assert!(matches!(
+        args.try_range::<i32>(1..5),
+        Err(ArgError::OutOfRange { len: 2, .. })
+    ));
+}
+
+#[test]
+fn test_try_getters_with_question_mark() {
+    let mut cmd = Command::new("app");
+    cmd.opt_option("-n, --number", "a number").action(|args| {
+        let number: i32 = args.try_get("--number")?;
+        assert_eq!(number, 3);
+        Ok(())
+    });
+
+    cmd.run_str(vec!["--number=3"]).unwrap();
+    let error = cmd.run_str(vec!["--number=three"]).unwrap_err();
+    assert!(error.downcast_ref::<ArgError>().is_some());
+}
+
+#[test]
+fn test_named_arguments() {
+    let mut cmd = Command::new("cp");
+    cmd.argument("<src>", "file to copy")
+        .argument("[dest]", "where to copy it")
+        .argument("[rest...]", "everything else");
+
+    assert_eq!(cmd.arguments[0].name.as_deref(), Some("src"));
+    assert!(cmd.arguments[0].required);
+    assert!(!cmd.arguments[1].required);
+    assert!(cmd.arguments[2].array);
+    assert_eq!(cmd.generate_usage(""), "cp <src> [dest] [rest...]");
+    assert!(
+        cmd.generate_args("", "\n")
+            .starts_with("src: file to copy (required)\n")
+    );
+
+    let (_, args, _) = Args::parse_str(&cmd, vec!["a.txt", "b.txt", "c", "d"]);
+    assert_eq!(args.value_of("src").unwrap(), "a.txt");
+    assert_eq!(args.value_of("dest").unwrap(), "b.txt");
+    assert_eq!(args.values_of("rest").unwrap(), ["c", "d"]);
+    assert!(args.value_of("other").is_none());
+
+    let (_, args, _) = Args::parse_str(&cmd, vec!["a.txt"]);
+    assert!(args.value_of("dest").is_none());
+    assert_eq!(args.values_of("rest").unwrap(), [] as [String; 0]);
+}
+
+#[test]
+fn test_variadic_argument_before_fixed() {
+    let mut cmd = Command::new("cp");
+    cmd.argument("<src>...", "files to copy")
+        .argument("<dest>", "where to copy them");
+
+    let (_, args, _) = Args::parse_str(&cmd, vec!["a", "b", "c", "dir"]);
+    assert_eq!(args.values_of("src").unwrap(), ["a", "b", "c"]);
+    assert_eq!(args.value_of("dest").unwrap(), "dir");
+
+    let (_, args, _) = Args::parse_str(&cmd, vec!["a", "dir"]);
+    assert_eq!(args.values_of("src").unwrap(), ["a"]);
+    assert_eq!(args.value_of("dest").unwrap(), "dir");
+
+    assert_eq!(cmd.generate_usage(""), "cp <src>... <dest>");
+}
+
+#[test]
+fn test_variadic_argument_counts() {
+    let mut cmd = Command::new("pick");
+    cmd.add_argument(
+        CLIArgument::named("[items...]", "items to pick")
+            .min(2)
+            .max(3),
+    )
+    .argument("[extra]", "one more")
+    .help(|_, _, _| {})
+    .action(|_| Ok(()));
+
+    let (_, args, _) = Args::parse_str(&cmd, vec!["a", "b", "c", "d"]);
+    assert_eq!(args.values_of("items").unwrap(), ["a", "b", "c"]);
+    assert_eq!(args.value_of("extra").unwrap(), "d");
+
+    cmd.run_str(vec!["a", "b"]).unwrap();
+    let error = cmd.run_str(vec!["a"]).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Expected between 2 and 3 [items...], got 1"
+    );
+
+    let mut cmd = Command::new("cp");
+    cmd.argument("<src>...", "files to copy")
+        .argument("<dest>", "where to copy them")
+        .help(|_, _, _| {})
+        .action(|_| Ok(()));
+
+    let error = cmd.run_str(vec!["dir"]).unwrap_err();
+    assert_eq!(error.to_string(), "Expected at least 1 <src>..., got 0");
+    let error = cmd.run_str(vec![]).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<CommandError>(),
+        Some(CommandError::ArgumentCount { got: 0, .. })
+    ));
+}
+
+#[test]
+fn test_unexpected_arguments() {
+    let mut cmd = Command::new("app");
+    cmd.argument("<file>", "a file")
+        .help(|_, _, _| {})
+        .action(|_| Ok(()));
+    cmd.command("build");
+
+    let error = cmd.run_str(vec!["a", "b", "c"]).unwrap_err();
+    match error.downcast_ref::<CommandError>() {
+        Some(CommandError::UnexpectedArgument {
+            index,
+            value,
+            suggestion,
+        }) => {
+            assert_eq!(*index, 1);
+            assert_eq!(value, "b");
+            assert!(suggestion.is_none());
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+
+    let error = cmd.run_str(vec!["a", "biuld"]).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Unexpected argument \"biuld\" at position 1, did you mean \"build\"?"
+    );
+
+    cmd.allow_extra_args(true);
+    cmd.run_str(vec!["a", "b", "c"]).unwrap();
+}
+
+#[test]
+fn test_option_specs() {
+    let option = CLIOption::new("-p, --port <number>", "port");
+    assert_eq!(option.names, vec!["-p", "--port"]);
+    assert_eq!(
+        option.value,
+        Some(ValueName {
+            name: "number".to_string(),
+            required: true
+        })
+    );
+
+    let option = CLIOption::new("--color [when]", "color");
+    assert_eq!(option.names, vec!["--color"]);
+    assert!(!option.value.unwrap().required);
+
+    let option = CLIOption::new("-v|--verbose", "verbose");
+    assert_eq!(option.names, vec!["-v", "--verbose"]);
+    assert!(option.value.is_none());
+
+    let error = CLIOption::parse("-p, --port <number", "port").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid spec \"-p, --port <number\": \"<number\" is missing a closing `>`"
+    );
+    assert!(CLIOption::parse("port", "port").is_err());
+    assert!(CLIOption::parse("-port", "port").is_err());
+    assert!(CLIOption::parse("<number>", "port").is_err());
+    assert!(CLIOption::parse("--", "port").is_err());
+    assert!(CLIOption::parse("--port <a> <b>", "port").is_err());
+}
+
+#[test]
+fn test_parse_option_values() {
+    let mut cmd = Command::new("app");
+    cmd.opt_option("-p, --port <number>", "port")
+        .opt_option("--color [when]", "color")
+        .opt_option("-v, --verbose", "verbose")
+        .allow_extra_args(true);
+    cmd.command("sub");
+
+    let (_, args, _) = Args::parse_str(&cmd, vec!["--port", "8080", "file"]);
+    assert_eq!(args.get_string("--port").unwrap(), "8080");
+    assert_eq!(args.pos, vec!["file"]);
+
+    let (_, args, _) = Args::parse_str(&cmd, vec!["-vp", "80"]);
+    assert_eq!(args.get_string("-v").unwrap(), "true");
+    assert_eq!(args.get_string("-p").unwrap(), "80");
+
+    let (_, args, _) = Args::parse_str(&cmd, vec!["-p8080", "--port=90"]);
+    assert_eq!(args.get_string("-p").unwrap(), "8080");
+    assert_eq!(args.get_string("--port").unwrap(), "90");
+
+    let (_, args, _) = Args::parse_str(&cmd, vec!["--color", "always", "--color"]);
+    assert_eq!(args.get_string("--color").unwrap(), "true");
+    let (_, args, _) = Args::parse_str(&cmd, vec!["--color", "always"]);
+    assert_eq!(args.get_string("--color").unwrap(), "always");
+    let (found, args, _) = Args::parse_str(&cmd, vec!["--color", "sub"]);
+    assert_eq!(args.get_string("--color").unwrap(), "true");
+    assert_eq!(found.names[0], "sub");
+
+    cmd.help(|_, _, _| {}).action(|_| Ok(()));
+    let error = cmd.run_str(vec!["--port"]).unwrap_err();
+    assert_eq!(error.to_string(), "Missing value for option --port");
+}
+
+#[test]
+fn test_command_specs() {
+    let mut cmd = Command::new("git");
+    cmd.command("clone <source> [dest]");
+    cmd.command("add <files...>");
+
+    let clone = &cmd.children[0];
+    assert_eq!(clone.names[0], "clone");
+    assert_eq!(clone.generate_usage(""), "clone <source> [dest]");
+    assert!(cmd.children[1].arguments[0].array);
+
+    let error = Command::from_spec("clone <source").err().unwrap();
+    assert_eq!(error.spec, "clone <source");
+    assert!(Command::from_spec("<source>").is_err());
+    assert!(Command::from_spec("clone source").is_err());
+    assert!(CLIArgument::parse("<a]", "").is_err());
+    assert!(CLIArgument::parse("<>", "").is_err());
+}
+
+#[test]
+fn test_from_usage() {
+    let text = "
+Greet people and add numbers.
+
+Usage:
+  human greet <names>...
+  human add -x=<n> -y=<n> [--verbose]
+  human add infinite [<numbers>...]
+  human -h | --help
+
+Options:
+  -x=<n>, --x=<n>  First number.
+  -y=<n>           Second number
+                   on two lines.
+  -v, --verbose    Print more [default: false].
+  --color WHEN     When to use colors.
+";
+    let mut cmd = Command::from_usage(text).unwrap();
+    assert_eq!(cmd.names[0], "human");
+    assert_eq!(cmd.desc.as_deref(), Some("Greet people and add numbers."));
+
+    let greet = &cmd.children[0];
+    assert_eq!(greet.names[0], "greet");
+    assert_eq!(greet.generate_usage(""), "greet <names>...");
+
+    let add = &cmd.children[1];
+    assert_eq!(add.options.len(), 3);
+    assert_eq!(add.options[0].names, vec!["-x", "--x"]);
+    assert_eq!(add.options[0].desc, "First number.");
+    assert!(add.options[0].required);
+    assert_eq!(add.options[1].desc, "Second number on two lines.");
+    assert!(!add.options[2].required);
+    assert_eq!(add.options[2].default, None);
+    assert_eq!(add.children[0].generate_usage(""), "infinite [numbers...]");
+
+    // options only in the options section go to the root command.
+    assert_eq!(cmd.options[0].names, vec!["--color"]);
+    assert_eq!(cmd.options[0].value.as_ref().unwrap().name, "when");
+
+    let captured = capture(cmd.find_command("add").unwrap());
+    cmd.run_str(vec!["add", "-x", "1", "-y=2"]).unwrap();
+    let args = captured.lock().unwrap().take().unwrap();
+    assert_eq!(args.get_string("-x").unwrap(), "1");
+    assert!(!args.has("--verbose"));
+}
+
+#[test]
+fn test_from_usage_errors() {
+    assert!(Command::from_usage("no usage here").is_err());
+    assert!(Command::from_usage("Usage: app [<file>").is_err());
+    assert!(Command::from_usage("Usage: app (<a> | <b>)").is_err());
+    assert!(Command::from_usage("Usage:\n  app add <a>\n  other add <a>").is_err());
+
+    let error = Command::from_usage("Usage: app <a> sub").err().unwrap();
+    assert_eq!(error.spec, "app <a> sub");
+}
+
+#[test]
+fn test_try_parse() {
+    let ran = Arc::new(Mutex::new(false));
+    let mut cmd = Command::new("app");
+    cmd.option("-v, --verbose", "Print more");
+    let ran_clone = ran.clone();
+    cmd.command("build <target>")
+        .option("--release", "Optimize")
+        .action(move |_| {
+            *ran_clone.lock().unwrap() = true;
+            Ok(())
+        });
+
+    let matches = cmd
+        .try_parse_str(vec!["build", "--verbose", "--release", "web"])
+        .unwrap();
+    assert!(!*ran.lock().unwrap());
+    assert_eq!(matches.path, vec!["app", "build"]);
+    assert_eq!(matches.subcommand(), Some("build"));
+    assert_eq!(matches.command(), "build");
+    assert!(matches.args.has("--verbose"));
+    assert_eq!(matches.args.value_of("target").unwrap(), "web");
+
+    let root = matches.level("app").unwrap();
+    assert!(root.has("--verbose"));
+    assert!(!root.has("--release"));
+    assert!(root.pos.is_empty());
+    let build = matches.level("build").unwrap();
+    assert!(!build.has("--verbose"));
+    assert!(build.has("--release"));
+    assert_eq!(build.pos, vec!["web"]);
+
+    assert!(cmd.try_parse_str(vec!["--help"]).unwrap().help_asked);
+
+    let error = cmd.try_parse_str(vec!["build", "--release"]).err().unwrap();
+    assert_eq!(error.path, vec!["app", "build"]);
+    assert!(matches!(error.kind, CommandError::MissingArgument { name } if name == "<target>"));
+}
+
+#[test]
+fn test_parent_positionals() {
+    let mut cmd = Command::new("app");
+    cmd.argument("[dir]", "Directory");
+    cmd.command("build <target>").add_argument(
+        CLIArgument::new("Jobs")
+            .required(false)
+            .parser(ValueParser::int()),
+    );
+
+    let matches = cmd.try_parse_str(vec!["foo", "build", "web", "4"]).unwrap();
+    assert_eq!(matches.args.value_of("target").unwrap(), "web");
+    assert_eq!(matches.args.value_at::<i64>(2), Some(&4));
+    assert_eq!(matches.level("app").unwrap().pos, vec!["foo"]);
+    let build = matches.level("build").unwrap();
+    assert_eq!(build.value_of("target").unwrap(), "web");
+    assert_eq!(build.value_at::<i64>(1), Some(&4));
+
+    let error = cmd.try_parse_str(vec!["foo", "build"]).err().unwrap();
+    assert!(matches!(error.kind, CommandError::MissingArgument { name } if name == "<target>"));
+    let error = cmd
+        .try_parse_str(vec!["foo", "build", "web", "four"])
+        .err()
+        .unwrap();
+    assert!(matches!(error.kind, CommandError::InvalidValue { value, .. } if value == "four"));
+    let error = cmd
+        .try_parse_str(vec!["foo", "build", "web", "4", "5"])
+        .err()
+        .unwrap();
+    assert!(matches!(error.kind, CommandError::UnexpectedArgument { value, .. } if value == "5"));
+}
+
+#[test]
+fn test_parse_errors() {
+    let mut cmd = Command::new("app");
+    cmd.command("build <target>")
+        .add_option(CLIOption::new("--release", "Optimize").required(false))
+        .add_option(
+            CLIOption::new("--debug", "Keep debug info")
+                .required(false)
+                .conflicts_with("--release"),
+        );
+
+    let error = cmd.try_parse_str(vec!["biuld"]).err().unwrap();
+    assert!(matches!(
+        error.kind,
+        CommandError::UnknownCommand { index: 0, .. }
+    ));
+    assert_eq!(error.token.as_deref(), Some("biuld"));
+    assert_eq!(error.suggestions, vec!["build"]);
+    assert_eq!(
+        error.to_string(),
+        "error: unknown command \"biuld\", did you mean \"build\"?\n\n\
+         \x20   app biuld\n\
+         \x20       ^^^^^\n\n\
+         usage: app <command>\n\n\
+         see `app --help` for more information."
+    );
+
+    let error = cmd
+        .try_parse_str(vec!["build", "web", "--relase"])
+        .err()
+        .unwrap();
+    assert_eq!(error.path, vec!["app", "build"]);
+    assert_eq!(error.usage, "app build [--options] <target>");
+    assert_eq!(
+        error.message(),
+        "unknown option --relase, did you mean \"--release\"?"
+    );
+
+    // single characters are too short to guess typos of.
+    let error = cmd.try_parse_str(vec!["build", "web", "-x"]).err().unwrap();
+    assert!(error.suggestions.is_empty());
+    let error = cmd.try_parse_str(vec!["build", "web", "-5"]).err().unwrap();
+    assert!(error.suggestions.is_empty());
+
+    let error = cmd
+        .try_parse_str(vec!["build", "web", "--debug", "--release"])
+        .err()
+        .unwrap();
+    assert_eq!(
+        error.message(),
+        "option --debug can't be used with --release"
+    );
+}
+
+#[test]
+fn test_error_carets() {
+    let mut cmd = Command::new("app");
+    cmd.add_option(CLIOption::new("-p, --port <n>", "Port").parser(ValueParser::int()))
+        .argument("<file>", "A file")
+        .add_argument(CLIArgument::named("[count]", "A count").parser(ValueParser::int()));
+
+    let error = cmd
+        .try_parse_str(vec!["a.txt", "--port=abc"])
+        .err()
+        .unwrap();
+    assert_eq!(error.index, Some(1));
+    assert_eq!(
+        error.annotate().unwrap(),
+        "    app a.txt --port=abc\n                     ^^^"
+    );
+
+    let error = cmd.try_parse_str(vec!["-p", "8x", "a.txt"]).err().unwrap();
+    assert_eq!(
+        error.annotate().unwrap(),
+        "    app -p 8x a.txt\n           ^^"
+    );
+
+    let error = cmd
+        .try_parse_str(vec!["--port", "1", "a.txt", "many"])
+        .err()
+        .unwrap();
+    assert_eq!(error.index, Some(3));
+    assert_eq!(
+        error.annotate().unwrap(),
+        "    app --port 1 a.txt many\n                       ^^^^"
+    );
+
+    let error = cmd.try_parse_str(vec!["a.txt", "--port"]).err().unwrap();
+    assert_eq!(error.index, Some(1));
+
+    // wide characters take two columns.
+    let error = cmd.try_parse_str(vec!["日本語", "--bad"]).err().unwrap();
+    assert_eq!(
+        error.annotate().unwrap(),
+        "    app 日本語 --bad\n               ^^^^^"
+    );
+    let error = cmd
+        .try_parse_str(vec!["--port=日本", "a.txt"])
+        .err()
+        .unwrap();
+    assert_eq!(
+        error.annotate().unwrap(),
+        "    app --port=日本 a.txt\n               ^^^^"
+    );
+
+    let error = cmd.try_parse_str(vec![]).err().unwrap();
+    assert!(error.annotate().is_none());
+
+    // running shows the same rendered error.
+    let err = Buffer::new();
+    cmd.configure_output(Output::new().stderr(err.clone()));
+    let error = cmd
+        .try_parse_str(vec!["--port=abc", "a.txt"])
+        .err()
+        .unwrap();
+    cmd.run_str(vec!["--port=abc", "a.txt"]).unwrap_err();
+    assert_eq!(err.contents(), format!("{error}\n"));
+    assert!(
+        err.contents()
+            .contains("    app --port=abc a.txt\n               ^^^\n")
+    );
+}
+
+#[test]
+fn test_collect_errors() {
+    let mut cmd = Command::new("app");
+    cmd.option("--name <name>", "Name")
+        .option("--count <n>", "Count")
+        .add_option(CLIOption::new("--level <n>", "Level").parser(ValueParser::int()))
+        .argument("<file>", "A file")
+        .help(|_, _, _| {})
+        .action(|_| Ok(()));
+
+    // stops at the first error by default.
+    let error = cmd.try_parse_str(vec!["--level", "high"]).err().unwrap();
+    assert!(matches!(error.kind, CommandError::MissingOption(_)));
+
+    cmd.collect_errors(true);
+    let error = cmd.run_str(vec!["--level", "high"]).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Found 4 errors:\n  \
+         missing option --name\n  \
+         missing option --count\n  \
+         missing argument <file>\n  \
+         invalid value \"high\" for --level: expected int: invalid digit found in string"
+    );
+
+    // rendered errors underline the token of every error that has one.
+    let error = cmd
+        .try_parse_str(vec!["--level", "high", "--count=x", "--count=y"])
+        .err()
+        .unwrap();
+    assert_eq!(
+        error.to_string(),
+        "error: found 3 errors:\n  \
+         missing option --name\n  \
+         missing argument <file>\n  \
+         invalid value \"high\" for --level: expected int: invalid digit found in string\n  \
+         \x20   app --level high --count=x --count=y\n  \
+         \x20               ^^^^\n\
+         \n\
+         usage: app --name <name> --count <n> --level <n> <file>\n\
+         \n\
+         see `app --help` for more information."
+    );
+
+    assert!(
+        cmd.try_parse_str(vec!["--name", "a", "--count", "1", "--level", "2", "a.txt"])
+            .is_ok()
+    );
+}
+
+#[derive(Debug)]
+/// an action error picking its own exit code, optionally caused by another error.
+struct Locked(Option<ExitError>);
+
+impl fmt::Display for Locked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "locked")
+    }
+}
+
+impl Error for Locked {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.as_ref().map(|error| error as _)
+    }
+}
+
+impl ExitStatus for Locked {
+    fn exit_code(&self) -> u8 {
+        42
+    }
+}
+
+#[test]
+fn test_exit_codes() {
+    let mut cmd = Command::new("app");
+    cmd.help(|_, _, _| {})
+        .config_option("--config")
+        .configure_output(Output::new().stderr(Buffer::new()));
+    cmd.command("ok").action(|_| Ok(()));
+    cmd.command("fail").action(|_| Err("broken".into()));
+    cmd.command("busy")
+        .action(|_| Err(ExitError::new(exit::TEMP_FAIL, "try again later").into()));
+    cmd.command("locked")
+        .action(|_| Err(ExitError::from_status(Locked(None)).into()));
+    cmd.command("wrapped")
+        .action(|_| Err(Locked(Some(ExitError::new(exit::NO_PERMISSION, "denied"))).into()));
+
+    let own = Buffer::new();
+    cmd.command("own")
+        .configure_output(Output::new().stderr(own.clone()))
+        .action(|_| Err("broken".into()));
+
+    let code = |args: Vec<&str>| cmd.exit_code(args.iter().map(|arg| arg.to_string()).collect());
+    assert_eq!(code(vec!["ok"]), exit::SUCCESS);
+    assert_eq!(code(vec!["--help"]), exit::SUCCESS);
+    assert_eq!(code(vec!["fail"]), exit::FAILURE);
+    assert_eq!(code(vec!["own"]), exit::FAILURE);
+    assert_eq!(own.contents(), "error: broken\n");
+    assert_eq!(code(vec!["busy"]), 75);
+    assert_eq!(code(vec!["locked"]), 42);
+    assert_eq!(code(vec!["wrapped"]), exit::NO_PERMISSION);
+    assert_eq!(code(vec!["nope"]), exit::USAGE);
+    assert_eq!(
+        code(vec!["--config", "/nonexistent/icicle.ini", "ok"]),
+        exit::CONFIG
+    );
+}
+
+#[test]
+fn test_configure_output() {
+    let out = Buffer::new();
+    let err = Buffer::new();
+    let mut cmd = Command::new("app");
+    cmd.configure_output(
+        Output::new()
+            .stdout(out.clone())
+            .stderr(err.clone())
+            .format_error(|message| format!("[{message}]")),
+    );
+    cmd.command("sub").option("--name <name>", "Name");
+
+    cmd.run_str(vec!["--help"]).unwrap();
+    assert!(out.contents().starts_with("usage: app"));
+    assert!(err.contents().is_empty());
+
+    // subcommands inherit the output.
+    cmd.run_str(vec!["sub"]).unwrap_err();
+    assert_eq!(
+        err.contents(),
+        "[error: missing option --name]\n\
+         \n\
+         usage: app sub --name <name>\n\
+         \n\
+         see `app sub --help` for more information.\n"
+    );
+}
+
+#[test]
+fn test_help_flags_and_command() {
+    let reasons = Arc::new(Mutex::new(Vec::new()));
+    let sink = reasons.clone();
+    let mut cmd = Command::new("human");
+    cmd.help(move |reason, command, _| {
+        sink.lock()
+            .unwrap()
+            .push(format!("{reason:?} {}", command.names[0]));
+    });
+    cmd.command("add")
+        .option("-x <n>", "First number")
+        .command("infinite");
+    let take = || std::mem::take(&mut *reasons.lock().unwrap());
+
+    cmd.run_str(vec!["--help"]).unwrap();
+    cmd.run_str(vec!["add", "-h"]).unwrap();
+    cmd.run_str(vec!["help", "add", "infinite"]).unwrap();
+    assert_eq!(
+        take(),
+        vec!["UserAsked human", "UserAsked add", "UserAsked infinite"]
+    );
+
+    let error = cmd.try_parse_str(vec!["help", "ad"]).err().unwrap();
+    assert_eq!(
+        error.message(),
+        "unknown command \"ad\", did you mean \"add\"?"
+    );
+
+    assert!(
+        cmd.generate_help()
+            .contains("  help [command...]  Show help for a command\n")
+    );
+
+    cmd.help_flags("-?, --usage");
+    cmd.run_str(vec!["add", "-?"]).unwrap();
+    assert_eq!(take(), vec!["UserAsked add"]);
+    assert!(cmd.try_parse_str(vec!["add", "-h"]).is_err());
+
+    cmd.disable_help_flags().help_command(false);
+    assert!(cmd.try_parse_str(vec!["--usage"]).is_err());
+    assert!(cmd.try_parse_str(vec!["help"]).is_err());
+    assert!(!cmd.generate_help().contains("help [command...]"));
+
+    // subcommands inherit whether the `help` subcommand is listed.
+    let out = Buffer::new();
+    let mut cmd = Command::new("human");
+    cmd.configure_output(Output::new().stdout(out.clone()))
+        .help_command(false);
+    cmd.command("add").command("infinite");
+    cmd.run_str(vec!["add", "--help"]).unwrap();
+    assert!(out.contents().contains("infinite"));
+    assert!(!out.contents().contains("help [command...]"));
+}
+
+#[test]
+fn test_version() {
+    let out = Buffer::new();
+    let mut cmd = Command::new("human");
+    cmd.configure_output(Output::new().stdout(out.clone()))
+        .version(crate::version!())
+        .version_metadata("commit", "abc123");
+    cmd.command("add").option("-x <n>", "First number");
+    cmd.command("legacy").version("0.1.0");
+
+    // short-circuits the missing -x option.
+    cmd.run_str(vec!["add", "-V"]).unwrap();
+    assert_eq!(
+        out.contents(),
+        format!("human add {}\n", env!("CARGO_PKG_VERSION"))
+    );
+
+    let matches = cmd.try_parse_str(vec!["legacy", "--version"]).unwrap();
+    assert!(matches.version_asked);
+    cmd.run_str(vec!["legacy", "--version"]).unwrap();
+    assert!(out.contents().ends_with("human legacy 0.1.0\n"));
+
+    cmd.version_flags("-v, --ver");
+    cmd.run_str(vec!["--ver"]).unwrap();
+    assert!(out.contents().ends_with("\ncommit: abc123\n"));
+    assert!(cmd.try_parse_str(vec!["add", "-x", "1", "-V"]).is_err());
+
+    let mut plain = Command::new("plain");
+    plain.action(|_| Ok(()));
+    assert!(plain.try_parse_str(vec!["--version"]).is_err());
+}
+
+#[test]
+fn test_help_layout() {
+    let mut cmd = Command::new("app");
+    cmd.help_width(40)
+        .add_option(
+            CLIOption::new("-n, --name <name>", "Name of the thing that is being made")
+                .required(false),
+        )
+        .add_option(CLIOption::new("--日本", "Wide").required(false))
+        .command("go")
+        .desc("Go");
+    assert_eq!(
+        cmd.generate_help(),
+        "usage: app [--options] <command>\n\
+         options:\n\
+         \x20 -n, --name <name>  Name of the thing\n\
+         \x20                    that is being made\n\
+         \x20                    (not required)\n\
+         \x20 --日本             Wide (not required)\n\
+         commands:\n\
+         \x20 go                 Go\n\
+         \x20 help [command...]  Show help for a\n\
+         \x20                    command\n"
+    );
+
+    // names too wide for the first column get their description on the next line.
+    let long = CLIOption::new("--a-very-long-option-name-indeed <value>", "Long").required(false);
+    let mut cmd = Command::new("app");
+    cmd.help_width(40).add_option(long);
+    assert!(
+        cmd.generate_help()
+            .contains("indeed <value>\n    Long (not required)\n")
+    );
+}
+
+#[test]
+fn test_color() {
+    let out = Buffer::new();
+    let err = Buffer::new();
+    let mut cmd = Command::new("app");
+    cmd.help_width(40)
+        .configure_output(Output::new().stdout(out.clone()).stderr(err.clone()))
+        .color(ColorChoice::Always)
+        .color_option("--color <when>")
+        .add_option(CLIOption::new("-n, --name <name>", "Name").required(false));
+    let plain = cmd.generate_help();
+
+    cmd.run_str(vec!["--help"]).unwrap();
+    let colored = out.contents();
+    assert!(colored.starts_with("\x1b[1;4musage:\x1b[0m app [--options]\n"));
+    assert!(colored.contains("\x1b[1;36m-n, --name\x1b[0m \x1b[32m<name>\x1b[0m  Name"));
+
+    // the option takes precedence over the command, and plain output is left unchanged.
+    let out = Buffer::new();
+    cmd.configure_output(Output::new().stdout(out.clone()).stderr(err.clone()));
+    cmd.run_str(vec!["--color", "never", "--help"]).unwrap();
+    assert_eq!(out.contents(), format!("{plain}\n"));
+
+    cmd.run_str(vec!["--color", "sometimes"]).unwrap_err();
+    assert!(err.contents().starts_with(
+        "\x1b[1;31merror: invalid value \"sometimes\" for --color: expected auto, always or never\x1b[0m\n\
+         \n\
+         \x20   app --color sometimes\n\
+         \x20               ^^^^^^^^^\n"
+    ));
+
+    // custom themes are used when color is on.
+    let out = Buffer::new();
+    let mut cmd = Command::new("app");
+    cmd.configure_output(Output::new().stdout(out.clone()))
+        .color(ColorChoice::Always)
+        .theme(Theme {
+            heading: Style::new().fg(Color::Magenta),
+            ..Theme::plain()
+        });
+    cmd.run_str(vec!["--help"]).unwrap();
+    assert_eq!(out.contents(), "\x1b[35musage:\x1b[0m app\n\n");
+
+    // subcommands can have their own color option.
+    let out = Buffer::new();
+    let mut cmd = Command::new("app");
+    cmd.configure_output(Output::new().stdout(out.clone()))
+        .color(ColorChoice::Never)
+        .command("build")
+        .color_option("--color <when>");
+    cmd.run_str(vec!["build", "--color", "always", "--help"])
+        .unwrap();
+    assert!(
+        out.contents()
+            .starts_with("\x1b[1;4musage:\x1b[0m app build")
+    );
+}
+
+#[test]
+fn test_help_sections() {
+    let mut cmd = Command::new("app");
+    cmd.help_width(80)
+        .add_option(CLIOption::new("-v", "Verbose").required(false))
+        .add_option(
+            CLIOption::new("--port <n>", "Port")
+                .required(false)
+                .heading("Network options"),
+        )
+        .add_option(
+            CLIOption::new("--host <name>", "Host")
+                .required(false)
+                .heading("Network options")
+                .order(0),
+        )
+        .add_option(CLIOption::new("-q", "Quiet").required(false).order(1));
+    cmd.command("pull").desc("Pull").category("Remote commands");
+    cmd.command("status").desc("Status");
+    cmd.command("push")
+        .desc("Push")
+        .category("Remote commands")
+        .order(0);
+
+    assert_eq!(
+        cmd.generate_help(),
+        "usage: app [--options] <command>\n\
+         options:\n\
+         \x20 -q  Quiet (not required)\n\
+         \x20 -v  Verbose (not required)\n\
+         Network options:\n\
+         \x20 --host <name>  Host (not required)\n\
+         \x20 --port <n>     Port (not required)\n\
+         commands:\n\
+         \x20 status             Status\n\
+         \x20 help [command...]  Show help for a command\n\
+         Remote commands:\n\
+         \x20 push  Push\n\
+         \x20 pull  Pull\n"
+    );
+}
+
+#[test]
+fn test_hidden_and_deprecated() {
+    let err = Buffer::new();
+    let mut cmd = Command::new("app");
+    cmd.help_width(80)
+        .configure_output(Output::new().stderr(err.clone()))
+        .add_option(CLIOption::new("-o, --output <file>", "Output file"))
+        .add_option(
+            CLIOption::new("--out <file>", "Output file")
+                .required(false)
+                .hidden(true)
+                .deprecated("use --output instead")
+                .alias_of("--output"),
+        )
+        .add_option(
+            CLIOption::new("--debug", "Debug")
+                .required(false)
+                .hidden(true),
+        )
+        .add_option(
+            CLIOption::new("--fast", "Fast")
+                .required(false)
+                .deprecated("it's always fast"),
+        );
+    cmd.command("get").desc("Get").hidden(true);
+    cmd.command("pull")
+        .desc("Pull")
+        .deprecated("use fetch instead")
+        .option("-o, --output <file>", "Output file");
+
+    assert_eq!(
+        cmd.generate_help(),
+        "usage: app -o <file> [--options] <command>\n\
+         options:\n\
+         \x20 -o, --output <file>  Output file (required)\n\
+         \x20 --fast               Fast (not required; deprecated, it's always fast)\n\
+         commands:\n\
+         \x20 pull               Pull (deprecated, use fetch instead)\n\
+         \x20 help [command...]  Show help for a command\n"
+    );
+
+    // the deprecated alias fills the option it stands for, and warns.
+    let args = run_capture(&mut cmd, vec!["--out", "a.txt", "--debug"]);
+    assert_eq!(args.get_string("--output"), Some(&"a.txt".to_string()));
+    assert_eq!(args.get_string("-o"), Some(&"a.txt".to_string()));
+    assert!(args.has("--debug"));
+    assert_eq!(
+        err.contents(),
+        "warning: --out is deprecated, use --output instead\n"
+    );
+
+    // hidden commands still run, and deprecated ones warn.
+    let err = Buffer::new();
+    cmd.configure_output(Output::new().stderr(err.clone()));
+    let get = capture(cmd.find_command("get").unwrap());
+    let pull = capture(cmd.find_command("pull").unwrap());
+    cmd.run_str(vec!["-o", "a", "get"]).unwrap();
+    cmd.run_str(vec!["-o", "a", "pull", "-o", "b"]).unwrap();
+    assert!(get.lock().unwrap().is_some() && pull.lock().unwrap().is_some());
+    assert_eq!(
+        err.contents(),
+        "warning: command pull is deprecated, use fetch instead\n"
+    );
+
+    // parsing without running reports warnings instead of printing them.
+    let err = Buffer::new();
+    cmd.configure_output(Output::new().stderr(err.clone()));
+    let matches = cmd.try_parse_str(vec!["--out", "a", "pull"]).ok().unwrap();
+    assert_eq!(
+        matches.warnings,
+        vec![
+            "command pull is deprecated, use fetch instead",
+            "--out is deprecated, use --output instead"
+        ]
+    );
+    assert!(err.contents().is_empty());
+
+    // hidden options and commands aren't suggested.
+    let error = cmd.try_parse_str(vec!["-o", "a", "gett"]).err().unwrap();
+    assert!(error.suggestions.is_empty());
+    let error = cmd
+        .try_parse_str(vec!["-o", "a", "--debugg"])
+        .err()
+        .unwrap();
+    assert!(error.suggestions.is_empty());
+}
+
+#[test]
+fn test_help_texts() {
+    let mut cmd = Command::new("git");
+    cmd.help_width(40)
+        .desc("The stupid content tracker")
+        .about("Tracks content in a repository of files and their history.\n\nSee the manual.")
+        .before_help("== git ==")
+        .after_help("Report bugs to the list.\n")
+        .example("git clone <url>", "Copy a repository")
+        .example("git pull", "Update");
+    cmd.command("clone")
+        .desc("Clone a repository into a new directory")
+        .summary("Clone a repository");
+
+    assert_eq!(
+        cmd.generate_help(),
+        "== git ==\n\
+         Tracks content in a repository of files\n\
+         and their history.\n\
+         \n\
+         See the manual.\n\
+         usage: git <command>\n\
+         commands:\n\
+         \x20 clone              Clone a repository\n\
+         \x20 help [command...]  Show help for a\n\
+         \x20                    command\n\
+         examples:\n\
+         \x20 git clone <url>  Copy a repository\n\
+         \x20 git pull         Update\n\
+         Report bugs to the list.\n"
+    );
+}
+
+#[test]
+fn test_help_template() {
+    let out = Buffer::new();
+    let mut cmd = Command::new("human");
+    cmd.help_width(80)
+        .version("1.2.3")
+        .configure_output(Output::new().stdout(out.clone()))
+        .about("Greets people.")
+        .help_template("{name} {version}\n{about}\n\nUSAGE:\n{usage}\n\n{options}\n{{done}}\n");
+    cmd.command("add")
+        .option("-x <n>", "First number")
+        .action(|_| Ok(()));
+
+    // subcommands inherit the template, and missing parts are empty.
+    cmd.run_str(vec!["add", "--help"]).unwrap();
+    assert_eq!(
+        out.contents(),
+        "human add 1.2.3\n\n\nUSAGE:\nhuman add -x <n>\n\noptions:\n  -x <n>  First number (required)\n{done}\n\n"
+    );
+    assert_eq!(
+        cmd.generate_help(),
+        "human 1.2.3\nGreets people.\n\nUSAGE:\nhuman <command>\n\n\n{done}\n"
+    );
+
+    // unknown placeholders and unmatched braces are caught when the template is set.
+    let help = cmd.generate_help();
+    let error = cmd.try_help_template("{usage}\n{flags}").err().unwrap();
+    assert!(error.message.starts_with("unknown placeholder `{flags}`"));
+    assert!(cmd.try_help_template("{usage").is_err());
+    assert!(cmd.try_help_template("usage}").is_err());
+    assert_eq!(cmd.generate_help(), help);
+}
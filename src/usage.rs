@@ -0,0 +1,418 @@
+use crate::{CLIArgument, CLIOption, Command, SpecError};
+
+/// an option described in the options section of a usage text.
+struct Described {
+    /// names of the option.
+    names: Vec<String>,
+    /// placeholder of the value the option takes, if any.
+    value: Option<String>,
+    /// description of the option.
+    desc: String,
+    /// default value, from `[default: value]` in the description.
+    default: Option<String>,
+}
+
+/// an option or argument found in a usage line.
+enum Item {
+    /// an option with its names, value placeholder and whether it is required.
+    Option(Vec<String>, Option<String>, bool),
+    /// an argument with its spec, like `<name>`, `[name]` or `<name>...`.
+    Argument(String),
+}
+
+/// a parsed usage line.
+struct UsageLine {
+    /// subcommand names after the program name.
+    path: Vec<String>,
+    /// options and arguments, in order.
+    items: Vec<Item>,
+    /// whether the line has `[options]`, which allows every described option.
+    all_options: bool,
+}
+
+/// which part of the usage text is being read.
+enum Section {
+    Description,
+    Usage,
+    Options,
+    Other,
+}
+
+/// creates an error for a line of the usage text.
+fn error(line: &str, message: &str) -> SpecError {
+    SpecError {
+        spec: line.to_string(),
+        message: message.to_string(),
+    }
+}
+
+/// checks if a usage token is a positional placeholder, like `<name>` or `NAME`.
+fn is_placeholder(token: &str) -> bool {
+    let token = token.strip_suffix("...").unwrap_or(token);
+    (token.starts_with('<') && token.ends_with('>'))
+        || (token.chars().any(|ch| ch.is_ascii_uppercase())
+            && token
+                .chars()
+                .all(|ch| ch.is_ascii_uppercase() || ch.is_ascii_digit() || ch == '_' || ch == '-'))
+}
+
+/// strips the brackets from a placeholder, turning `<n>`, `<n>...` and `N` into `n`.
+fn placeholder_name(token: &str) -> String {
+    let token = token.strip_suffix("...").unwrap_or(token);
+    let token = token.trim_start_matches('<').trim_end_matches('>');
+    if token.starts_with(|ch: char| ch.is_ascii_uppercase()) {
+        token.to_lowercase()
+    } else {
+        token.to_string()
+    }
+}
+
+/// parses a line of the options section, like `-x=<n>, --x=<n>  First number [default: 0].`
+fn parse_described(line: &str) -> Result<Described, SpecError> {
+    // the spec and description are separated by at least two spaces or a tab.
+    let split = [line.find("  "), line.find('\t')]
+        .into_iter()
+        .flatten()
+        .min();
+    let (spec, desc) = match split {
+        Some(index) => (&line[..index], line[index..].trim()),
+        None => (line, ""),
+    };
+
+    let mut names = Vec::new();
+    let mut value = None;
+    for token in spec
+        .split([',', ' ', '='])
+        .filter(|token| !token.is_empty())
+    {
+        if token.starts_with('-') {
+            names.push(token.to_string());
+        } else if is_placeholder(token) {
+            value = Some(placeholder_name(token));
+        } else {
+            return Err(error(line, &format!("unexpected {token:?} in option")));
+        }
+    }
+
+    let lower = desc.to_lowercase();
+    let default = lower.find("[default:").and_then(|start| {
+        let rest = &desc[start + "[default:".len()..];
+        rest.find(']').map(|end| rest[..end].trim().to_string())
+    });
+    // a flag that is off by default is just left out.
+    let default = default.filter(|default| value.is_some() || default != "false");
+
+    Ok(Described {
+        names,
+        value,
+        desc: desc.to_string(),
+        default,
+    })
+}
+
+/// splits a usage line into tokens, keeping brackets, parentheses and `|` apart.
+fn tokenize(line: &str) -> Vec<String> {
+    let mut spaced = String::new();
+    for ch in line.chars() {
+        if "[]()|".contains(ch) {
+            spaced.push(' ');
+            spaced.push(ch);
+            spaced.push(' ');
+        } else {
+            spaced.push(ch);
+        }
+    }
+
+    spaced.split_whitespace().map(str::to_string).collect()
+}
+
+/// parses a usage line, without the program name.
+fn parse_line(
+    line: &str,
+    tokens: &[String],
+    described: &[Described],
+) -> Result<UsageLine, SpecError> {
+    let mut usage = UsageLine {
+        path: Vec::new(),
+        items: Vec::new(),
+        all_options: false,
+    };
+    let mut depth = 0;
+    let mut in_commands = true;
+
+    let mut tokens = tokens.iter().peekable();
+    while let Some(token) = tokens.next() {
+        let token = token.as_str();
+        match token {
+            "[" => depth += 1,
+            "]" => {
+                if depth == 0 {
+                    return Err(error(line, "unmatched `]`"));
+                }
+                depth -= 1;
+            }
+            "(" | ")" => {}
+            "|" => return Err(error(line, "alternatives with `|` aren't supported")),
+            "..." => match usage.items.last_mut() {
+                Some(Item::Argument(spec)) if !spec.ends_with("...") => spec.push_str("..."),
+                _ => return Err(error(line, "`...` must follow an argument")),
+            },
+            "options" if depth > 0 => {
+                in_commands = false;
+                usage.all_options = true;
+            }
+            _ if token.starts_with('-') && token.len() > 1 => {
+                in_commands = false;
+                let (name, attached) = match token.split_once('=') {
+                    Some((name, value)) => (name, Some(placeholder_name(value))),
+                    None => (token, None),
+                };
+
+                // splits stacked short flags like `-vq`.
+                let names: Vec<String> = if !name.starts_with("--") && name.len() > 2 {
+                    name.chars().skip(1).map(|ch| format!("-{ch}")).collect()
+                } else {
+                    vec![name.to_string()]
+                };
+
+                for name in names {
+                    let takes_value = described
+                        .iter()
+                        .find(|option| option.names.contains(&name))
+                        .is_some_and(|option| option.value.is_some());
+                    let value = match attached.clone() {
+                        Some(value) => Some(value),
+                        None if takes_value => tokens
+                            .next_if(|next| is_placeholder(next))
+                            .map(|next| placeholder_name(next)),
+                        None => None,
+                    };
+                    usage
+                        .items
+                        .push(Item::Option(vec![name], value, depth == 0));
+                }
+            }
+            _ if is_placeholder(token) => {
+                in_commands = false;
+                let name = placeholder_name(token);
+                let spec = if depth == 0 {
+                    format!("<{name}>")
+                } else {
+                    format!("[{name}]")
+                };
+                let array = if token.ends_with("...") { "..." } else { "" };
+                usage.items.push(Item::Argument(format!("{spec}{array}")));
+            }
+            _ if in_commands && depth == 0 => usage.path.push(token.to_string()),
+            _ => {
+                return Err(error(
+                    line,
+                    &format!(
+                        "{token:?} should come before options and arguments, or be a placeholder"
+                    ),
+                ));
+            }
+        }
+    }
+
+    if depth != 0 {
+        return Err(error(line, "unmatched `[`"));
+    }
+
+    Ok(usage)
+}
+
+/// finds a subcommand by name, creating it if it doesn't exist.
+fn child<'a>(command: &'a mut Command, name: &str) -> &'a mut Command {
+    match command
+        .children
+        .iter()
+        .position(|child| child.names[0] == name)
+    {
+        Some(index) => &mut command.children[index],
+        None => command.command(name),
+    }
+}
+
+/// adds an option to a command, merging it with an option that has the same names.
+fn add_option(command: &mut Command, option: CLIOption) {
+    match command.options.iter_mut().find(|existing| {
+        existing
+            .names
+            .iter()
+            .any(|name| option.names.contains(name))
+    }) {
+        // the option is only required if every usage line requires it.
+        Some(existing) => existing.required &= option.required,
+        None => command.options.push(option),
+    }
+}
+
+/// creates an option from its names and value placeholder, filled in with its description.
+fn build_option(
+    line: &str,
+    names: &[String],
+    value: Option<&String>,
+    described: Option<&Described>,
+) -> Result<CLIOption, SpecError> {
+    let names = described.map_or(names, |described| &described.names);
+    let mut spec = names.join(", ");
+    if let Some(value) = value.or(described.and_then(|described| described.value.as_ref())) {
+        spec.push_str(&format!(" <{value}>"));
+    }
+
+    let desc = described.map_or("", |described| described.desc.as_str());
+    let mut option = CLIOption::parse(&spec, desc).map_err(|spec_error| SpecError {
+        spec: line.to_string(),
+        ..spec_error
+    })?;
+    if let Some(default) = described.and_then(|described| described.default.as_ref()) {
+        option = option.default(default);
+    }
+
+    Ok(option)
+}
+
+/// builds a command tree from a docopt-like usage text.
+pub(crate) fn parse_usage(text: &str) -> Result<Command, SpecError> {
+    let mut description = Vec::new();
+    let mut lines = Vec::new();
+    let mut described: Vec<Described> = Vec::new();
+
+    let mut section = Section::Description;
+    for raw in text.lines() {
+        let line = raw.trim();
+        let lower = line.to_lowercase();
+        if lower.starts_with("usage:") {
+            section = Section::Usage;
+            let rest = line["usage:".len()..].trim();
+            if !rest.is_empty() {
+                lines.push(rest);
+            }
+            continue;
+        }
+        if lower.ends_with(':') && lower.contains("options") {
+            section = Section::Options;
+            continue;
+        }
+
+        match section {
+            Section::Description if lines.is_empty() => {
+                if !line.is_empty() {
+                    description.push(line);
+                } else if !description.is_empty() {
+                    section = Section::Other;
+                }
+            }
+            Section::Usage if line.is_empty() => section = Section::Other,
+            Section::Usage => lines.push(line),
+            Section::Options if line.starts_with('-') => described.push(parse_described(line)?),
+            // continues the description of the previous option.
+            Section::Options if !line.is_empty() => {
+                if let Some(last) = described.last_mut() {
+                    last.desc.push(' ');
+                    last.desc.push_str(line);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let first = lines
+        .first()
+        .ok_or_else(|| error(text.trim(), "no usage lines found after `Usage:`"))?;
+    let program = first
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let mut root = Command::new(&program);
+    if !description.is_empty() {
+        root.desc(&description.join(" "));
+    }
+
+    let mut used = vec![false; described.len()];
+    let mut all_options_paths = Vec::new();
+    for line in lines {
+        let tokens = tokenize(line);
+        if tokens.first() != Some(&program) {
+            return Err(error(
+                line,
+                &format!("usage lines must start with {program:?}"),
+            ));
+        }
+
+        // skips lines that only show the help or version flags, which icicle handles itself.
+        let flags = ["-h", "--help", "-V", "--version", "|", "(", ")", "[", "]"];
+        if tokens.len() > 1
+            && tokens[1..]
+                .iter()
+                .all(|token| flags.contains(&token.as_str()))
+        {
+            continue;
+        }
+
+        let usage = parse_line(line, &tokens[1..], &described)?;
+        let mut command = &mut root;
+        for name in usage.path.iter() {
+            command = child(command, name);
+        }
+        if usage.all_options {
+            all_options_paths.push(usage.path.clone());
+        }
+
+        let mut arguments = Vec::new();
+        for item in usage.items.iter() {
+            match item {
+                Item::Option(names, value, required) => {
+                    let index = described
+                        .iter()
+                        .position(|option| option.names.iter().any(|name| names.contains(name)));
+                    if let Some(index) = index {
+                        used[index] = true;
+                    }
+                    let option =
+                        build_option(line, names, value.as_ref(), index.map(|i| &described[i]))?;
+                    add_option(command, option.required(*required));
+                }
+                Item::Argument(spec) => arguments.push(CLIArgument::parse(spec, "")?),
+            }
+        }
+
+        if command.arguments.is_empty() {
+            command.arguments = arguments;
+        } else if !arguments.is_empty()
+            && command
+                .arguments
+                .iter()
+                .map(|arg| arg.usage(0))
+                .collect::<Vec<_>>()
+                != arguments.iter().map(|arg| arg.usage(0)).collect::<Vec<_>>()
+        {
+            return Err(error(
+                line,
+                "usage lines for the same command must have the same arguments",
+            ));
+        }
+    }
+
+    // described options not used in any line go to the commands with `[options]`, or the root.
+    if all_options_paths.is_empty() {
+        all_options_paths.push(Vec::new());
+    }
+    for (option, used) in described.iter().zip(used) {
+        if used {
+            continue;
+        }
+        for path in all_options_paths.iter() {
+            let mut command = &mut root;
+            for name in path {
+                command = child(command, name);
+            }
+            let built = build_option(&option.names.join(", "), &[], None, Some(option))?;
+            add_option(command, built.required(false));
+        }
+    }
+
+    Ok(root)
+}
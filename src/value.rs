@@ -0,0 +1,116 @@
+use core::fmt;
+use std::{any::Any, path::PathBuf, str::FromStr, sync::Arc};
+
+/// a parsed value of any type.
+pub(crate) type AnyValue = Arc<dyn Any + Send + Sync>;
+/// function that parses a string into a typed value.
+type ParseFn = Arc<dyn Fn(&str) -> Result<AnyValue, String> + Send + Sync>;
+
+#[derive(Clone)]
+/// a check run on an option or argument value before the action, with the message shown when it
+/// fails.
+pub struct Validator {
+    /// function that returns whether a value is valid.
+    check: Arc<dyn Fn(&str) -> bool + Send + Sync>,
+    /// reason shown when the check fails.
+    pub message: String,
+}
+
+impl Validator {
+    /// creates a validator from a check and a failure message.
+    pub fn new<T: Fn(&str) -> bool + Send + Sync + 'static>(check: T, message: &str) -> Validator {
+        Validator {
+            check: Arc::new(check),
+            message: message.to_string(),
+        }
+    }
+
+    /// runs the check, returning the failure message if the value is invalid.
+    pub fn validate(&self, value: &str) -> Result<(), String> {
+        if (self.check)(value) {
+            Ok(())
+        } else {
+            Err(self.message.clone())
+        }
+    }
+}
+
+impl fmt::Debug for Validator {
+    /// formats a validator, which only shows its message.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Validator")
+            .field("message", &self.message)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Clone)]
+/// the type of an option or argument value, checked when the command runs.
+pub struct ValueParser {
+    /// name of the type, shown in errors.
+    pub name: String,
+    /// function that parses a value into the type.
+    parse: ParseFn,
+}
+
+impl ValueParser {
+    /// creates a parser for any type implementing `FromStr`.
+    pub fn of<T>() -> ValueParser
+    where
+        T: FromStr + Send + Sync + 'static,
+        T::Err: fmt::Display,
+    {
+        Self::named::<T>(std::any::type_name::<T>())
+    }
+
+    /// creates a parser for any type implementing `FromStr`, with a name shown in errors.
+    pub fn named<T>(name: &str) -> ValueParser
+    where
+        T: FromStr + Send + Sync + 'static,
+        T::Err: fmt::Display,
+    {
+        ValueParser {
+            name: name.to_string(),
+            parse: Arc::new(|value| {
+                value
+                    .parse::<T>()
+                    .map(|parsed| Arc::new(parsed) as AnyValue)
+                    .map_err(|error| error.to_string())
+            }),
+        }
+    }
+
+    /// parses integers into `i64`.
+    pub fn int() -> ValueParser {
+        Self::named::<i64>("int")
+    }
+
+    /// parses floating point numbers into `f64`.
+    pub fn float() -> ValueParser {
+        Self::named::<f64>("float")
+    }
+
+    /// parses `true` or `false` into `bool`.
+    pub fn bool() -> ValueParser {
+        Self::named::<bool>("bool")
+    }
+
+    /// parses paths into `PathBuf`.
+    pub fn path() -> ValueParser {
+        Self::named::<PathBuf>("path")
+    }
+
+    /// parses a value, returning a message with the underlying error if it fails.
+    pub(crate) fn parse(&self, value: &str) -> Result<AnyValue, String> {
+        (self.parse)(value).map_err(|error| format!("expected {}: {}", self.name, error))
+    }
+}
+
+impl fmt::Debug for ValueParser {
+    /// formats a value parser, which only shows its type name.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ValueParser")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}